@@ -0,0 +1,566 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use aide::gen::GenContext;
+use aide::openapi::Operation;
+use aide::OperationInput;
+use async_trait::async_trait;
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::{header, request::Parts, HeaderMap};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use snafu::OptionExt;
+
+use crate::error::{AppError, InvalidJwtKeySnafu, InvalidTokenSnafu, Result, UnauthorizedSnafu};
+use crate::views::permission::Identity;
+
+/// name of the bearer security scheme in the openapi docs
+pub const BEARER_AUTH: &str = "bearerAuth";
+
+/// claim of the token type, tokens of [`REFRESH_TOKEN_TYPE`] are rejected by [`jwt_authenticate`]
+pub const TOKEN_TYPE_CLAIM: &str = "token_type";
+
+pub const REFRESH_TOKEN_TYPE: &str = "refresh";
+
+/// claims of a validated token, inserted into the request extensions by [`jwt_authenticate`],
+/// extract it in a handler to require an authenticated request, or `Option<Claims>` to allow anonymous
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Claims {
+    /// subject of the token, usually the user id
+    pub sub: String,
+    /// expiration time as a unix timestamp
+    pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// other claims like `aud`
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Claims {
+    /// the [`TOKEN_TYPE_CLAIM`] of the token, like `access` or `refresh`
+    pub fn token_type(&self) -> Option<&str> {
+        self.extra.get(TOKEN_TYPE_CLAIM).and_then(Value::as_str)
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Claims {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        parts
+            .extensions
+            .get::<Claims>()
+            .cloned()
+            .context(UnauthorizedSnafu {
+                reason: "authentication credentials were not provided",
+            })
+    }
+}
+
+impl OperationInput for Claims {
+    fn operation_input(_ctx: &mut GenContext, operation: &mut Operation) {
+        let requirement = [(BEARER_AUTH.to_owned(), vec![])].into_iter().collect();
+        if !operation.security.contains(&requirement) {
+            operation.security.push(requirement);
+        }
+    }
+}
+
+/// store of the revoked tokens, tokens found in it are rejected by [`jwt_authenticate`],
+/// see [`JwtAuth::revocation`]
+#[async_trait]
+pub trait RevocationStore: Send + Sync {
+    async fn is_revoked(&self, claims: &Claims) -> Result<bool>;
+}
+
+#[derive(Clone)]
+struct SignKey {
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+#[derive(Clone)]
+struct VerifyKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+/// validate HS256, RS256 and EdDSA bearer tokens with the configured keys,
+/// `exp` and `nbf` are always checked, `aud` and `iss` are checked if configured,
+/// tokens are signed with the HS256 secret or the private key if configured
+#[derive(Clone, Default)]
+pub struct JwtAuth {
+    keys: Arc<Vec<VerifyKey>>,
+    signer: Option<SignKey>,
+    audience: Vec<String>,
+    issuer: Vec<String>,
+    leeway: u64,
+    revocation: Option<Arc<dyn RevocationStore>>,
+}
+
+impl JwtAuth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// read the keys and validation options from env keys if exists.
+    ///
+    /// env **`JWT_SECRET`** the HS256 secret
+    ///
+    /// env **`JWT_RSA_PUBLIC_KEY_FILE`** path of the RS256 public key pem file
+    ///
+    /// env **`JWT_ED_PUBLIC_KEY_FILE`** path of the EdDSA public key pem file
+    ///
+    /// env **`JWT_RSA_PRIVATE_KEY_FILE`** and **`JWT_ED_PRIVATE_KEY_FILE`** path of the private key pem file
+    /// to sign tokens, the public key must be configured too
+    ///
+    /// env **`JWT_JWKS_FILE`** path of a local JWKS json file
+    ///
+    /// env **`JWT_AUDIENCE`** and **`JWT_ISSUER`** the allowed `aud` and `iss`, separated by `,`
+    ///
+    /// env **`JWT_LEEWAY`** seconds of the clock skew allowed, default is `0`
+    pub fn from_env() -> Result<Self> {
+        let env = |key: &str| std::env::var(key).ok().filter(|value| !value.is_empty());
+        let split = |value: String| {
+            value
+                .split(',')
+                .map(|item| item.trim().to_owned())
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>()
+        };
+        let mut auth = Self::new();
+        if let Some(secret) = env("JWT_SECRET") {
+            auth = auth.hs256(secret.as_bytes());
+        }
+        if let Some(path) = env("JWT_RSA_PUBLIC_KEY_FILE") {
+            auth = auth.rs256_pem(&read_key_file(&path)?)?;
+        }
+        if let Some(path) = env("JWT_ED_PUBLIC_KEY_FILE") {
+            auth = auth.ed_dsa_pem(&read_key_file(&path)?)?;
+        }
+        if let Some(path) = env("JWT_RSA_PRIVATE_KEY_FILE") {
+            auth = auth.rs256_private_pem(&read_key_file(&path)?)?;
+        }
+        if let Some(path) = env("JWT_ED_PRIVATE_KEY_FILE") {
+            auth = auth.ed_dsa_private_pem(&read_key_file(&path)?)?;
+        }
+        if let Some(path) = env("JWT_JWKS_FILE") {
+            auth = auth.jwks_file(path)?;
+        }
+        if let Some(audience) = env("JWT_AUDIENCE") {
+            auth = auth.audience(split(audience));
+        }
+        if let Some(issuer) = env("JWT_ISSUER") {
+            auth = auth.issuer(split(issuer));
+        }
+        if let Some(leeway) = env("JWT_LEEWAY") {
+            auth = auth.leeway(leeway.parse().ok().context(InvalidJwtKeySnafu {
+                reason: "JWT_LEEWAY must be seconds",
+            })?);
+        }
+        Ok(auth)
+    }
+
+    fn key(mut self, kid: Option<String>, algorithm: Algorithm, key: DecodingKey) -> Self {
+        Arc::make_mut(&mut self.keys).push(VerifyKey {
+            kid,
+            algorithm,
+            key,
+        });
+        self
+    }
+
+    /// validate tokens with the secret, and sign tokens with it if no other signing key
+    pub fn hs256(mut self, secret: &[u8]) -> Self {
+        if self.signer.is_none() {
+            self.signer = Some(SignKey {
+                algorithm: Algorithm::HS256,
+                key: EncodingKey::from_secret(secret),
+            });
+        }
+        self.key(None, Algorithm::HS256, DecodingKey::from_secret(secret))
+    }
+
+    fn signer(
+        mut self,
+        algorithm: Algorithm,
+        key: jsonwebtoken::errors::Result<EncodingKey>,
+    ) -> Result<Self> {
+        let key = key.map_err(|e| {
+            InvalidJwtKeySnafu {
+                reason: e.to_string(),
+            }
+            .build()
+        })?;
+        self.signer = Some(SignKey { algorithm, key });
+        Ok(self)
+    }
+
+    /// sign tokens with the RS256 private key
+    pub fn rs256_private_pem(self, pem: &[u8]) -> Result<Self> {
+        self.signer(Algorithm::RS256, EncodingKey::from_rsa_pem(pem))
+    }
+
+    /// sign tokens with the EdDSA private key
+    pub fn ed_dsa_private_pem(self, pem: &[u8]) -> Result<Self> {
+        self.signer(Algorithm::EdDSA, EncodingKey::from_ed_pem(pem))
+    }
+
+    pub fn rs256_pem(self, pem: &[u8]) -> Result<Self> {
+        let key = DecodingKey::from_rsa_pem(pem).map_err(|e| {
+            InvalidJwtKeySnafu {
+                reason: e.to_string(),
+            }
+            .build()
+        })?;
+        Ok(self.key(None, Algorithm::RS256, key))
+    }
+
+    pub fn ed_dsa_pem(self, pem: &[u8]) -> Result<Self> {
+        let key = DecodingKey::from_ed_pem(pem).map_err(|e| {
+            InvalidJwtKeySnafu {
+                reason: e.to_string(),
+            }
+            .build()
+        })?;
+        Ok(self.key(None, Algorithm::EdDSA, key))
+    }
+
+    /// add the RSA, octet and Ed25519 keys of the set, a key is matched by the `kid` header if set
+    pub fn jwks(mut self, jwks: &JwkSet) -> Result<Self> {
+        for jwk in &jwks.keys {
+            let algorithm = match jwk.algorithm {
+                AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                AlgorithmParameters::OctetKey(_) => Algorithm::HS256,
+                AlgorithmParameters::OctetKeyPair(_) => Algorithm::EdDSA,
+                AlgorithmParameters::EllipticCurve(_) => {
+                    tracing::warn!(
+                        "skip unsupported elliptic curve jwk {:?}",
+                        jwk.common.key_id
+                    );
+                    continue;
+                }
+            };
+            let key = DecodingKey::from_jwk(jwk).map_err(|e| {
+                InvalidJwtKeySnafu {
+                    reason: e.to_string(),
+                }
+                .build()
+            })?;
+            self = self.key(jwk.common.key_id.clone(), algorithm, key);
+        }
+        Ok(self)
+    }
+
+    /// add the keys of a local JWKS json file
+    pub fn jwks_file(self, path: impl AsRef<Path>) -> Result<Self> {
+        let content = read_key_file(path)?;
+        let jwks = serde_json::from_slice::<JwkSet>(&content).map_err(|e| {
+            InvalidJwtKeySnafu {
+                reason: e.to_string(),
+            }
+            .build()
+        })?;
+        self.jwks(&jwks)
+    }
+
+    pub fn audience(mut self, audience: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.audience = audience.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn issuer(mut self, issuer: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.issuer = issuer.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn leeway(mut self, seconds: u64) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// reject the tokens revoked in the store, like the access token revoked by `/auth/logout`
+    pub fn revocation(mut self, store: impl RevocationStore + 'static) -> Self {
+        self.revocation = Some(Arc::new(store));
+        self
+    }
+
+    /// whether the token is revoked in the [`RevocationStore`], false if no store is configured
+    pub async fn is_revoked(&self, claims: &Claims) -> Result<bool> {
+        match &self.revocation {
+            Some(store) => store.is_revoked(claims).await,
+            None => Ok(false),
+        }
+    }
+
+    fn validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = self.leeway;
+        validation.validate_nbf = true;
+        validation.validate_aud = !self.audience.is_empty();
+        if !self.audience.is_empty() {
+            validation.set_audience(&self.audience);
+        }
+        if !self.issuer.is_empty() {
+            validation.set_issuer(&self.issuer);
+        }
+        validation
+    }
+
+    /// sign the claims, the first configured issuer and audience are set if absent
+    pub fn encode(&self, claims: &Claims) -> Result<String> {
+        let signer = self.signer.as_ref().context(InvalidJwtKeySnafu {
+            reason: "no signing key",
+        })?;
+        let mut claims = claims.clone();
+        if claims.iss.is_none() {
+            claims.iss = self.issuer.first().cloned();
+        }
+        if let Some(audience) = self.audience.first() {
+            claims
+                .extra
+                .entry("aud")
+                .or_insert_with(|| audience.as_str().into());
+        }
+        encode(&Header::new(signer.algorithm), &claims, &signer.key).map_err(|e| {
+            InvalidJwtKeySnafu {
+                reason: e.to_string(),
+            }
+            .build()
+        })
+    }
+
+    /// validate the token with the keys of the same algorithm and `kid`, return 401 if all failed
+    pub fn decode(&self, token: &str) -> Result<Claims> {
+        let header = decode_header(token).map_err(|e| {
+            InvalidTokenSnafu {
+                reason: e.to_string(),
+            }
+            .build()
+        })?;
+        let mut error = None;
+        for key in self.keys.iter().filter(|key| {
+            key.algorithm == header.alg && (header.kid.is_none() || key.kid == header.kid)
+        }) {
+            match decode::<Claims>(token, &key.key, &self.validation(key.algorithm)) {
+                Ok(data) => return Ok(data.claims),
+                Err(e) => error = Some(e),
+            }
+        }
+        InvalidTokenSnafu {
+            reason: error.map_or_else(
+                || format!("no key for algorithm {:?}", header.alg),
+                |e| e.to_string(),
+            ),
+        }
+        .fail()
+    }
+}
+
+fn read_key_file(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    let path = path.as_ref();
+    std::fs::read(path).map_err(|e| {
+        InvalidJwtKeySnafu {
+            reason: format!("read {}: {e}", path.display()),
+        }
+        .build()
+    })
+}
+
+/// the token of an `Authorization: Bearer <token>` header
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then_some(token.trim())
+}
+
+/// authenticate requests with a bearer token, requests without one pass as anonymous,
+/// and the permissions of the view decide whether they are allowed
+/// ```rust,no_run
+/// use axum::{middleware, Router};
+/// use axum_restful::auth::{jwt_authenticate, JwtAuth};
+///
+/// let auth = JwtAuth::from_env().unwrap();
+/// let app: Router = Router::new().layer(middleware::from_fn_with_state(auth, jwt_authenticate));
+/// ```
+/// return 401 with a `WWW-Authenticate` header if the token is invalid, revoked or a refresh token,
+/// or insert the [`Claims`] and an [`Identity`] of `sub` into the request extensions
+pub async fn jwt_authenticate(
+    State(auth): State<JwtAuth>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(token) = bearer_token(request.headers()) {
+        match auth.decode(token) {
+            Ok(claims) if claims.token_type() == Some(REFRESH_TOKEN_TYPE) => {
+                return InvalidTokenSnafu {
+                    reason: "refresh token can not be used for authentication",
+                }
+                .build()
+                .into_response();
+            }
+            Ok(claims) => {
+                match auth.is_revoked(&claims).await {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        return InvalidTokenSnafu {
+                            reason: "token has been revoked",
+                        }
+                        .build()
+                        .into_response();
+                    }
+                    Err(e) => return e.into_response(),
+                }
+                request.extensions_mut().insert(Identity {
+                    subject: claims.sub.clone(),
+                });
+                request.extensions_mut().insert(claims);
+            }
+            Err(e) => return e.into_response(),
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TestClient;
+    use axum::{http::StatusCode, middleware, routing::get, Router};
+
+    fn now() -> u64 {
+        jsonwebtoken::get_current_timestamp()
+    }
+
+    fn token(claims: Value) -> String {
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn validate_claims() {
+        let auth = JwtAuth::new()
+            .hs256(b"secret")
+            .audience(["api"])
+            .issuer(["auth"]);
+        let valid =
+            serde_json::json!({"sub": "bob", "exp": now() + 60, "aud": "api", "iss": "auth"});
+        let claims = auth.decode(&token(valid.clone())).unwrap();
+        assert_eq!(claims.sub, "bob");
+        assert_eq!(claims.extra["aud"], "api");
+
+        let mut expired = valid.clone();
+        expired["exp"] = (now() - 120).into();
+        let mut immature = valid.clone();
+        immature["nbf"] = (now() + 120).into();
+        let mut audience = valid.clone();
+        audience["aud"] = "other".into();
+        let mut issuer = valid.clone();
+        issuer["iss"] = "other".into();
+        for claims in [expired, immature, audience, issuer] {
+            assert!(matches!(
+                auth.decode(&token(claims)),
+                Err(AppError::InvalidToken { .. })
+            ));
+        }
+        assert!(JwtAuth::new()
+            .hs256(b"other")
+            .decode(&token(valid))
+            .is_err());
+
+        let claims = Claims {
+            sub: "bob".to_owned(),
+            exp: now() + 60,
+            nbf: None,
+            iat: None,
+            iss: None,
+            jti: None,
+            extra: Map::new(),
+        };
+        let signed = auth.decode(&auth.encode(&claims).unwrap()).unwrap();
+        assert_eq!(signed.iss.as_deref(), Some("auth"));
+        assert_eq!(signed.extra["aud"], "api");
+    }
+
+    #[tokio::test]
+    async fn authenticate_bearer_token() {
+        let auth = JwtAuth::new().hs256(b"secret");
+        let app = Router::new()
+            .route("/me", get(|claims: Claims| async move { claims.sub }))
+            .layer(middleware::from_fn_with_state(auth, jwt_authenticate));
+        let client = TestClient::new(app);
+
+        let res = client.get("/me").send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(res.headers()[header::WWW_AUTHENTICATE], "Bearer");
+
+        let res = client
+            .get("/me")
+            .header(header::AUTHORIZATION, "Bearer invalid")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer error="invalid_token""#
+        );
+
+        let token = token(serde_json::json!({"sub": "bob", "exp": now() + 60}));
+        let res = client
+            .get("/me")
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await, "bob");
+    }
+
+    struct RevokedBob;
+
+    #[async_trait]
+    impl RevocationStore for RevokedBob {
+        async fn is_revoked(&self, claims: &Claims) -> Result<bool> {
+            Ok(claims.sub == "bob")
+        }
+    }
+
+    #[tokio::test]
+    async fn reject_revoked_token() {
+        let auth = JwtAuth::new().hs256(b"secret").revocation(RevokedBob);
+        let app = Router::new()
+            .route("/me", get(|claims: Claims| async move { claims.sub }))
+            .layer(middleware::from_fn_with_state(auth, jwt_authenticate));
+        let client = TestClient::new(app);
+        for (sub, status) in [("bob", StatusCode::UNAUTHORIZED), ("alice", StatusCode::OK)] {
+            let token = token(serde_json::json!({"sub": sub, "exp": now() + 60}));
+            let res = client
+                .get("/me")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .send()
+                .await;
+            assert_eq!(res.status(), status);
+        }
+    }
+}
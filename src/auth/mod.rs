@@ -0,0 +1,7 @@
+pub mod jwt;
+#[cfg(feature = "users")]
+pub mod users;
+
+pub use jwt::{jwt_authenticate, Claims, JwtAuth, RevocationStore, BEARER_AUTH};
+#[cfg(feature = "users")]
+pub use users::UserAuth;
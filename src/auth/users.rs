@@ -0,0 +1,528 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use aide::axum::{
+    routing::{get_with, post_with},
+    ApiRouter,
+};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use async_trait::async_trait;
+use axum::{extract::State, http::StatusCode, Json, Router};
+use jsonwebtoken::get_current_timestamp;
+use schemars::JsonSchema;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    Schema, Set, SqlErr,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Map;
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::auth::jwt::{Claims, JwtAuth, RevocationStore, REFRESH_TOKEN_TYPE, TOKEN_TYPE_CLAIM};
+use crate::db;
+use crate::error::{
+    ForbiddenSnafu, InternalServerSnafu, InvalidTokenSnafu, OperateDatabaseSnafu, Result,
+    UnauthorizedSnafu,
+};
+use crate::views::JsonBody;
+
+pub const ACCESS_TOKEN_TYPE: &str = "access";
+
+pub mod user {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "auth_user")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i64,
+        #[sea_orm(unique)]
+        pub username: String,
+        /// argon2 phc string, see [`super::hash_password`]
+        #[serde(skip_serializing)]
+        pub password_hash: String,
+        pub is_active: bool,
+        pub is_superuser: bool,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod revoked_token {
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    /// `jti` of the revoked tokens, rows can be removed after `expires_at`
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+    #[sea_orm(table_name = "auth_revoked_token")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub jti: String,
+        /// unix timestamp of the token expiration
+        pub expires_at: i64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// create the user and revoked token tables if not exists
+pub async fn migrate(db: &DatabaseConnection) -> Result<()> {
+    let backend = db.get_database_backend();
+    let schema = Schema::new(backend);
+    for mut statement in [
+        schema.create_table_from_entity(user::Entity),
+        schema.create_table_from_entity(revoked_token::Entity),
+    ] {
+        db.execute(backend.build(statement.if_not_exists()))
+            .await
+            .context(OperateDatabaseSnafu)?;
+    }
+    Ok(())
+}
+
+/// hash the password into an argon2 phc string with a random salt
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| {
+            tracing::error!("hash password failed: {e}");
+            InternalServerSnafu.build()
+        })
+}
+
+/// hash verified for a missing user, so the login takes as long as with a wrong password
+fn dummy_hash() -> &'static str {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    DUMMY_HASH.get_or_init(|| hash_password("dummy password").unwrap_or_default())
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LoginBody {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RefreshBody {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// always `Bearer`
+    pub token_type: String,
+    /// seconds until the access token expires
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct UserOutput {
+    pub id: i64,
+    pub username: String,
+    pub is_active: bool,
+    pub is_superuser: bool,
+}
+
+impl From<user::Model> for UserOutput {
+    fn from(model: user::Model) -> Self {
+        Self {
+            id: model.id,
+            username: model.username,
+            is_active: model.is_active,
+            is_superuser: model.is_superuser,
+        }
+    }
+}
+
+/// `/auth/login`, `/auth/refresh`, `/auth/logout` and `/auth/me` routes of the [`user`] entity,
+/// tokens are signed and validated by the [`JwtAuth`], the `sub` of a token is the user id,
+/// set it as the [`JwtAuth::revocation`] of the middleware to reject the tokens revoked by logout
+/// ```rust,no_run
+/// use axum::{middleware, Router};
+/// use axum_restful::auth::{jwt_authenticate, JwtAuth, UserAuth};
+///
+/// let jwt = JwtAuth::from_env().unwrap();
+/// let users = UserAuth::new(jwt.clone());
+/// let app: Router = users
+///     .clone()
+///     .router()
+///     .layer(middleware::from_fn_with_state(jwt.revocation(users), jwt_authenticate));
+/// ```
+#[derive(Clone)]
+pub struct UserAuth {
+    jwt: JwtAuth,
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+    db: Option<&'static DatabaseConnection>,
+}
+
+impl UserAuth {
+    /// access tokens expire in 15 minutes and refresh tokens in 7 days by default
+    pub fn new(jwt: JwtAuth) -> Self {
+        Self {
+            jwt,
+            access_ttl: Duration::from_secs(15 * 60),
+            refresh_ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            db: None,
+        }
+    }
+
+    pub fn access_ttl(mut self, ttl: Duration) -> Self {
+        self.access_ttl = ttl;
+        self
+    }
+
+    pub fn refresh_ttl(mut self, ttl: Duration) -> Self {
+        self.refresh_ttl = ttl;
+        self
+    }
+
+    /// use the connection instead of [`db::get_db_connection_pool`]
+    pub fn db(mut self, db: &'static DatabaseConnection) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    async fn get_db_connection(&self) -> &'static DatabaseConnection {
+        match self.db {
+            Some(db) => db,
+            None => db::get_db_connection_pool().await,
+        }
+    }
+
+    /// create an active user with the hashed password
+    pub async fn create_user(&self, username: &str, password: &str) -> Result<user::Model> {
+        user::ActiveModel {
+            username: Set(username.to_owned()),
+            password_hash: Set(hash_password(password)?),
+            is_active: Set(true),
+            is_superuser: Set(false),
+            ..Default::default()
+        }
+        .insert(self.get_db_connection().await)
+        .await
+        .context(OperateDatabaseSnafu)
+    }
+
+    fn claims(&self, user: &user::Model, token_type: &str, ttl: Duration) -> Claims {
+        let now = get_current_timestamp();
+        let mut jti = [0u8; 16];
+        OsRng.fill_bytes(&mut jti);
+        let mut extra = Map::new();
+        extra.insert(TOKEN_TYPE_CLAIM.to_owned(), token_type.into());
+        Claims {
+            sub: user.id.to_string(),
+            exp: now + ttl.as_secs(),
+            nbf: None,
+            iat: Some(now),
+            iss: None,
+            jti: Some(jti.iter().map(|b| format!("{b:02x}")).collect()),
+            extra,
+        }
+    }
+
+    fn issue(&self, user: &user::Model) -> Result<TokenPair> {
+        Ok(TokenPair {
+            access_token: self.jwt.encode(&self.claims(
+                user,
+                ACCESS_TOKEN_TYPE,
+                self.access_ttl,
+            ))?,
+            refresh_token: self.jwt.encode(&self.claims(
+                user,
+                REFRESH_TOKEN_TYPE,
+                self.refresh_ttl,
+            ))?,
+            token_type: "Bearer".to_owned(),
+            expires_in: self.access_ttl.as_secs(),
+        })
+    }
+
+    /// the active user of the token `sub`, return 401 if not found or inactive
+    async fn active_user(&self, claims: &Claims) -> Result<user::Model> {
+        let id = claims.sub.parse::<i64>().ok().context(InvalidTokenSnafu {
+            reason: "invalid subject",
+        })?;
+        user::Entity::find_by_id(id)
+            .filter(user::Column::IsActive.eq(true))
+            .one(self.get_db_connection().await)
+            .await
+            .context(OperateDatabaseSnafu)?
+            .context(UnauthorizedSnafu {
+                reason: "user not found or inactive",
+            })
+    }
+
+    /// revoke the token, return false if it has been revoked
+    async fn revoke(&self, claims: &Claims) -> Result<bool> {
+        let jti = claims.jti.clone().context(InvalidTokenSnafu {
+            reason: "token without jti can not be revoked",
+        })?;
+        let result = revoked_token::Entity::insert(revoked_token::ActiveModel {
+            jti: Set(jti),
+            expires_at: Set(claims.exp as i64),
+        })
+        .exec(self.get_db_connection().await)
+        .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(OperateDatabaseSnafu),
+        }
+    }
+
+    fn refresh_claims(&self, token: &str) -> Result<Claims> {
+        let claims = self.jwt.decode(token)?;
+        ensure!(
+            claims.token_type() == Some(REFRESH_TOKEN_TYPE),
+            InvalidTokenSnafu {
+                reason: "expect a refresh token",
+            }
+        );
+        Ok(claims)
+    }
+
+    /// api routers with swagger docs, merge them next to the model view routers
+    pub fn api_router(self) -> ApiRouter {
+        ApiRouter::new()
+            .api_route(
+                "/auth/login",
+                post_with(login, |op| {
+                    op.summary("login with username and password")
+                        .description("return an access and refresh token pair, or 401")
+                }),
+            )
+            .api_route(
+                "/auth/refresh",
+                post_with(refresh, |op| {
+                    op.summary("exchange a refresh token for a new token pair")
+                        .description("the refresh token is revoked after it is used")
+                }),
+            )
+            .api_route(
+                "/auth/logout",
+                post_with(logout, |op| {
+                    op.summary("revoke the refresh token and the current access token")
+                }),
+            )
+            .api_route(
+                "/auth/me",
+                get_with(me, |op| op.summary("fetch the current user")),
+            )
+            .with_state(self)
+    }
+
+    pub fn router(self) -> Router {
+        self.api_router().into()
+    }
+}
+
+#[async_trait]
+impl RevocationStore for UserAuth {
+    async fn is_revoked(&self, claims: &Claims) -> Result<bool> {
+        let Some(jti) = &claims.jti else {
+            return Ok(false);
+        };
+        Ok(revoked_token::Entity::find_by_id(jti.to_owned())
+            .one(self.get_db_connection().await)
+            .await
+            .context(OperateDatabaseSnafu)?
+            .is_some())
+    }
+}
+
+async fn login(
+    State(auth): State<UserAuth>,
+    JsonBody(body): JsonBody<LoginBody>,
+) -> Result<Json<TokenPair>> {
+    let user = user::Entity::find()
+        .filter(user::Column::Username.eq(body.username.as_str()))
+        .one(auth.get_db_connection().await)
+        .await
+        .context(OperateDatabaseSnafu)?;
+    let hash = user.as_ref().map(|user| user.password_hash.clone());
+    // argon2 is slow on purpose, verify out of the async runtime
+    let verified = tokio::task::spawn_blocking(move || {
+        let hash = match &hash {
+            Some(hash) => hash.as_str(),
+            None => dummy_hash(),
+        };
+        verify_password(&body.password, hash)
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!("verify password failed: {e}");
+        InternalServerSnafu.build()
+    })?;
+    let user = user
+        .filter(|user| user.is_active && verified)
+        .context(UnauthorizedSnafu {
+            reason: "invalid username or password",
+        })?;
+    tracing::debug!("user {} login", user.id);
+    Ok(Json(auth.issue(&user)?))
+}
+
+async fn refresh(
+    State(auth): State<UserAuth>,
+    JsonBody(body): JsonBody<RefreshBody>,
+) -> Result<Json<TokenPair>> {
+    let claims = auth.refresh_claims(&body.refresh_token)?;
+    let user = auth.active_user(&claims).await?;
+    ensure!(
+        auth.revoke(&claims).await?,
+        InvalidTokenSnafu {
+            reason: "token has been revoked",
+        }
+    );
+    Ok(Json(auth.issue(&user)?))
+}
+
+async fn logout(
+    State(auth): State<UserAuth>,
+    access: Claims,
+    JsonBody(body): JsonBody<RefreshBody>,
+) -> Result<StatusCode> {
+    let claims = auth.refresh_claims(&body.refresh_token)?;
+    ensure!(
+        claims.sub == access.sub,
+        ForbiddenSnafu {
+            reason: "the refresh token belongs to another user",
+        }
+    );
+    auth.revoke(&claims).await?;
+    if access.jti.is_some() {
+        auth.revoke(&access).await?;
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn me(State(auth): State<UserAuth>, claims: Claims) -> Result<Json<UserOutput>> {
+    ensure!(
+        !auth.is_revoked(&claims).await?,
+        InvalidTokenSnafu {
+            reason: "token has been revoked",
+        }
+    );
+    Ok(Json(auth.active_user(&claims).await?.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt_authenticate;
+    use crate::test_helpers::TestClient;
+    use axum::{http::header, middleware};
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use tokio::sync::OnceCell;
+
+    static USER_DB: OnceCell<DatabaseConnection> = OnceCell::const_new();
+
+    fn bob() -> user::Model {
+        user::Model {
+            id: 1,
+            username: "bob".to_owned(),
+            password_hash: hash_password("secret").unwrap(),
+            is_active: true,
+            is_superuser: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn login_and_fetch_current_user() {
+        let db = USER_DB
+            .get_or_init(|| async {
+                MockDatabase::new(DatabaseBackend::Postgres)
+                    .append_query_results([[bob()], [bob()]])
+                    .append_query_results([Vec::<revoked_token::Model>::new()])
+                    .append_query_results([[bob()]])
+                    .append_query_results([Vec::<user::Model>::new()])
+                    .into_connection()
+            })
+            .await;
+        let jwt = JwtAuth::new().hs256(b"secret");
+        let app = UserAuth::new(jwt.clone())
+            .db(db)
+            .router()
+            .layer(middleware::from_fn_with_state(
+                jwt.clone(),
+                jwt_authenticate,
+            ));
+        let client = TestClient::new(app);
+
+        let login = |password: &'static str| {
+            client
+                .post("/auth/login")
+                .json(&serde_json::json!({"username": "bob", "password": password}))
+                .send()
+        };
+        let res = login("secret").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let tokens = res.json::<TokenPair>().await;
+        let claims = jwt.decode(&tokens.access_token).unwrap();
+        assert_eq!(claims.sub, "1");
+        assert_eq!(claims.token_type(), Some(ACCESS_TOKEN_TYPE));
+        assert_eq!(login("wrong").await.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .get("/auth/me")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", tokens.refresh_token),
+            )
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = client
+            .get("/auth/me")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", tokens.access_token),
+            )
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.json::<UserOutput>().await.username, "bob");
+
+        let res = client
+            .post("/auth/refresh")
+            .json(&RefreshBody {
+                refresh_token: tokens.access_token,
+            })
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post("/auth/login")
+            .json(&serde_json::json!({"username": "alice", "password": "secret"}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+}
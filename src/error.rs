@@ -1,10 +1,11 @@
 use aide::gen::GenContext;
 use aide::openapi::Operation;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 
 use aide::OperationOutput;
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -13,6 +14,8 @@ use sea_orm::DbErr;
 use serde::Serialize;
 use snafu::{Location, Snafu};
 
+use crate::views::validation::ValidationErrors;
+
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub))]
 pub enum AppError {
@@ -23,11 +26,49 @@ pub enum AppError {
     CreateInstance { source: DbErr, location: Location },
 
     #[snafu(display("instance not found with primary key: {}", pk))]
-    PrimaryKeyNotFound { pk: u64, location: Location },
+    PrimaryKeyNotFound { pk: String, location: Location },
 
     #[snafu(display("query database failed: {}", source))]
     OperateDatabase { source: DbErr, location: Location },
 
+    #[snafu(display("invalid request body: {}", reason))]
+    InvalidBody { reason: String, location: Location },
+
+    #[snafu(display("invalid field `{}`: {}", field, reason))]
+    InvalidField {
+        field: String,
+        reason: String,
+        location: Location,
+    },
+
+    #[snafu(display("unauthorized: {}", reason))]
+    Unauthorized { reason: String, location: Location },
+
+    #[snafu(display("invalid token: {}", reason))]
+    InvalidToken { reason: String, location: Location },
+
+    #[snafu(display("invalid jwt key: {}", reason))]
+    InvalidJwtKey { reason: String, location: Location },
+
+    #[snafu(display("permission denied: {}", reason))]
+    Forbidden { reason: String, location: Location },
+
+    #[snafu(display("precondition failed: {}", reason))]
+    PreconditionFailed { reason: String, location: Location },
+
+    #[snafu(display("route prefix `{}` conflicts with `{}`", prefix, existing))]
+    PrefixConflict {
+        prefix: String,
+        existing: String,
+        location: Location,
+    },
+
+    #[snafu(display("validation failed: {}", errors))]
+    Validation {
+        errors: ValidationErrors,
+        location: Location,
+    },
+
     #[snafu(display("option value is none"))]
     OptionValueNone { location: Location },
 
@@ -40,20 +81,57 @@ pub struct ErrorMessage {
     pub message: String,
 }
 
+/// body of [`AppError::Validation`], messages of every invalid field
+#[derive(Debug, JsonSchema, Serialize)]
+pub struct ValidationMessage {
+    pub message: String,
+    pub errors: BTreeMap<String, Vec<String>>,
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let status_code = match self {
             AppError::PrimaryKeyNotFound { .. } => StatusCode::NOT_FOUND,
+            AppError::InvalidBody { .. }
+            | AppError::InvalidField { .. }
+            | AppError::Validation { .. } => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized { .. } | AppError::InvalidToken { .. } => {
+                StatusCode::UNAUTHORIZED
+            }
+            AppError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            AppError::PreconditionFailed { .. } => StatusCode::PRECONDITION_FAILED,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         };
+        let authenticate = match self {
+            AppError::Unauthorized { .. } => Some("Bearer"),
+            AppError::InvalidToken { .. } => Some(r#"Bearer error="invalid_token""#),
+            _ => None,
+        };
         tracing::error!("error happened: {self:?}");
-        (
-            status_code,
-            Json(ErrorMessage {
-                message: format!("{}", self),
-            }),
-        )
-            .into_response()
+        let mut response = match self {
+            AppError::Validation { errors, .. } => (
+                status_code,
+                Json(ValidationMessage {
+                    message: "validation failed".to_owned(),
+                    errors: errors.errors,
+                }),
+            )
+                .into_response(),
+            _ => (
+                status_code,
+                Json(ErrorMessage {
+                    message: format!("{}", self),
+                }),
+            )
+                .into_response(),
+        };
+        if let Some(authenticate) = authenticate {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(authenticate),
+            );
+        }
+        response
     }
 }
 
@@ -1,10 +1,13 @@
 #![cfg_attr(nightly_error_messages, feature(rustc_attrs))]
 //! axum A restful framework based on `axum` and `sea-orm`. Inspired by `django-rest-framework`.
 //! The goal of the project is to build an enterprise-level production framework.
+pub mod auth;
 pub mod db;
 pub mod error;
 pub mod swagger;
 pub mod test_helpers;
+#[cfg(test)]
+mod tests_cfg;
 pub mod utils;
 pub mod views;
 
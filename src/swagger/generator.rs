@@ -1,24 +1,143 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
 use std::sync::Arc;
 
 use aide::{
     axum::{
-        routing::{get, get_with},
+        routing::{get, ApiMethodRouter},
         ApiRouter,
     },
-    openapi::OpenApi,
+    openapi::{OpenApi, SecurityRequirement, SecurityScheme},
+    operation::OperationHandler,
     transform::{TransformOpenApi, TransformOperation},
+    OperationInput, OperationOutput,
 };
 use async_trait::async_trait;
 use axum::{
+    extract::{Path, Query},
+    handler::Handler,
+    http::{header, Method},
     response::{IntoResponse, Response},
+    routing::MethodRouter,
     Extension, Json, Router,
 };
-use schemars::{gen, JsonSchema};
-use sea_orm::{ActiveModelBehavior, ActiveModelTrait, EntityTrait, IntoActiveModel};
+use schemars::{
+    gen,
+    schema::{InstanceType, Schema, SingleOrVec},
+    JsonSchema,
+};
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, EntityTrait, IdenStatic, IntoActiveModel, RelationTrait,
+};
 use serde::Serialize;
 use tower_http::services::ServeDir;
 
-use crate::views::ModelViewExt;
+use crate::auth::BEARER_AUTH;
+use crate::swagger::params::{
+    header_parameter, path_parameter, query_parameter, response_header, with_etag_header,
+    with_location_header, with_parameters,
+};
+use crate::views::actions::{ActionBody, ActionTarget};
+use crate::views::expand::{expanded_schema, Expand};
+use crate::views::pagination::{Page, PaginationStyle, TOTAL_COUNT_HEADER};
+use crate::views::{
+    Action, BulkItemResult, CursorPage, ModelSchema, ModelViewExt, ParentEntity, PrimaryKeyValue,
+    RequestParts, ResponseBody, ViewSchema,
+};
+
+/// route the documented methods of the paths, a path without any enabled method is left out of
+/// the docs and answers 405 with an empty `Allow` header
+fn api_routes<S>(
+    routes: impl IntoIterator<Item = (String, Option<ApiMethodRouter<S>>)>,
+) -> ApiRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    routes.into_iter().fold(
+        ApiRouter::new(),
+        |router, (path, method_router)| match method_router {
+            Some(method_router) => router.api_route(&path, method_router),
+            None => router.route(&path, MethodRouter::new()),
+        },
+    )
+}
+
+/// route the documented handler of the method, panic if the method is not supported
+fn api_method_with<H, I, O, T, F>(
+    router: ApiMethodRouter,
+    method: &Method,
+    handler: H,
+    docs: F,
+) -> ApiMethodRouter
+where
+    H: Handler<T, ()> + OperationHandler<I, O>,
+    I: OperationInput,
+    O: OperationOutput,
+    T: 'static,
+    F: FnOnce(TransformOperation) -> TransformOperation,
+{
+    match *method {
+        Method::GET => router.get_with(handler, docs),
+        Method::POST => router.post_with(handler, docs),
+        Method::PUT => router.put_with(handler, docs),
+        Method::PATCH => router.patch_with(handler, docs),
+        Method::DELETE => router.delete_with(handler, docs),
+        Method::HEAD => router.head_with(handler, docs),
+        Method::OPTIONS => router.options_with(handler, docs),
+        Method::TRACE => router.trace_with(handler, docs),
+        _ => panic!("unsupported method of the extra action: {method}"),
+    }
+}
+
+/// extract the swagger ui files, the ui loads the openapi document from `../api.json`
+pub(crate) async fn init_swagger_files() -> anyhow::Result<()> {
+    awesome_operates::extract_all_files!(awesome_operates::embed::Asset);
+    awesome_operates::swagger::InitSwagger::new(
+        awesome_operates::embed::EXTRACT_SWAGGER_DIR_PATH,
+        "swagger-init.js",
+        "index.html",
+        "../api.json",
+    )
+    .build()
+    .await?;
+    Ok(())
+}
+
+/// serve the openapi document in the [`Extension`]
+pub(crate) async fn serve_openapi(Extension(api): Extension<Arc<OpenApi>>) -> Response {
+    Json(serde_json::json!(*api)).into_response()
+}
+
+/// title of the openapi document and the jwt bearer security scheme
+pub(crate) fn default_docs_config(api: TransformOpenApi) -> TransformOpenApi {
+    bearer_security_config(
+        api.title("Aide axum Open API for axum-restful")
+            .summary("axum-restful openapi"),
+    )
+}
+
+/// register the jwt bearer security scheme, see [`crate::auth::jwt_authenticate`]
+pub(crate) fn bearer_security_config(mut api: TransformOpenApi) -> TransformOpenApi {
+    api = api.security_scheme(
+        BEARER_AUTH,
+        SecurityScheme::Http {
+            scheme: "bearer".to_owned(),
+            bearer_format: Some("JWT".to_owned()),
+            description: Some("jwt bearer token in the `Authorization` header".to_owned()),
+            extensions: Default::default(),
+        },
+    );
+    let security = &mut api.inner_mut().security;
+    for requirement in [
+        [(BEARER_AUTH.to_owned(), vec![])].into_iter().collect(),
+        SecurityRequirement::default(),
+    ] {
+        if !security.contains(&requirement) {
+            security.push(requirement);
+        }
+    }
+    api
+}
 
 /// generate swagger docs for service
 /// when the service is up
@@ -30,12 +149,18 @@ use crate::views::ModelViewExt;
 /// http://{ipaddress}:{port}/docs/openapi
 /// ```
 #[async_trait]
-pub trait SwaggerGeneratorExt<T>: 'static + ModelViewExt<T>
+pub trait SwaggerGeneratorExt<T, S = ModelSchema>: 'static + ModelViewExt<T, S>
 where
     Self: Send + JsonSchema,
     T: ActiveModelTrait + ActiveModelBehavior + Send + 'static + Sync,
+    S: ViewSchema<T> + 'static,
+    S::Create: JsonSchema,
+    S::Update: JsonSchema,
+    S::Output: JsonSchema,
     <T::Entity as EntityTrait>::Model: IntoActiveModel<T> + Serialize + Sync + JsonSchema,
     for<'de> <T::Entity as EntityTrait>::Model: serde::de::Deserialize<'de>,
+    PrimaryKeyValue<T>: Serialize + Clone + Sync + JsonSchema,
+    for<'de> PrimaryKeyValue<T>: serde::de::Deserialize<'de>,
 {
     fn modle_schema_description() -> String {
         let mut gen = gen::SchemaGenerator::default();
@@ -57,22 +182,70 @@ where
         "/docs/openapi/api.json"
     }
 
-    async fn serve_docs(Extension(api): Extension<Arc<OpenApi>>) -> Response {
-        Json(serde_json::json!(*api)).into_response()
+    async fn serve_docs(api: Extension<Arc<OpenApi>>) -> Response {
+        serve_openapi(api).await
     }
 
     fn api_docs_head_config(api: TransformOpenApi) -> TransformOpenApi {
-        api.title("Aide axum Open API for axum-restful")
-            .summary("axum-restful openapi")
+        default_docs_config(api)
+    }
+
+    /// register the jwt bearer security scheme, see [`crate::auth::jwt_authenticate`],
+    /// a request can be anonymous or authenticated with a bearer token
+    fn api_security_config(api: TransformOpenApi) -> TransformOpenApi {
+        bearer_security_config(api)
     }
 
     fn http_retrieve_summary() -> String {
         format!("fetch an instance: {}", Self::modle_schema_description())
     }
 
+    /// document the primary key path params of the detail route with the schema of the primary key type,
+    /// every part of a composite primary key is a separate path param
+    fn http_detail_path_docs(op: TransformOperation) -> TransformOperation {
+        let mut gen = gen::SchemaGenerator::default();
+        let schema = gen.subschema_for::<PrimaryKeyValue<T>>();
+        let params = Self::primary_key_path_params();
+        let schemas = if params.len() > 1 {
+            match schema.into_object().array.map(|array| array.items) {
+                Some(Some(SingleOrVec::Vec(items))) => items,
+                _ => vec![Schema::Bool(true); params.len()],
+            }
+        } else {
+            vec![schema]
+        };
+        let parameters = params.iter().zip(schemas).map(|(param, schema)| {
+            path_parameter(
+                param,
+                &format!("primary key `{param}` of {}", Self::modle_name()),
+                schema,
+            )
+        });
+        with_parameters(op, parameters)
+    }
+
     fn http_retrieve_docs(op: TransformOperation) -> TransformOperation {
-        op.summary(&Self::http_retrieve_summary())
-            .response::<200, Json<<T::Entity as EntityTrait>::Model>>()
+        let op = Self::http_expand_docs(Self::http_fields_docs(Self::http_detail_path_docs(op)))
+            .summary(&Self::http_retrieve_summary());
+        let op = Self::http_include_deleted_docs(op)
+            .response_with::<304, (), _>(|res| res.description("the instance is not modified"));
+        if Self::expand_relations().is_empty() {
+            op.response_with::<200, Json<S::Output>, _>(with_etag_header)
+        } else {
+            op.response_with::<200, Json<ExpandedOutput<Self, T, S>>, _>(with_etag_header)
+        }
+    }
+
+    /// document the `If-Match` header of the writes
+    fn http_if_match_docs(op: TransformOperation) -> TransformOperation {
+        with_parameters(
+            op,
+            [header_parameter(
+                header::IF_MATCH.as_str(),
+                "`ETag` of the retrieved instance, return 412 if the instance is changed since",
+                InstanceType::String,
+            )],
+        )
     }
 
     fn http_update_summary() -> String {
@@ -80,8 +253,25 @@ where
     }
 
     fn http_update_docs(op: TransformOperation) -> TransformOperation {
-        op.summary(&Self::http_update_summary())
-            .response::<200, ()>()
+        let op = Self::http_if_match_docs(Self::http_detail_path_docs(op))
+            .summary(&Self::http_update_summary());
+        match Self::update_response() {
+            ResponseBody::Empty => op.response::<200, ()>(),
+            ResponseBody::Instance => op.response::<200, Json<S::Output>>(),
+        }
+    }
+
+    fn http_partial_update_summary() -> String {
+        format!(
+            "partial update an instance {}",
+            Self::modle_schema_description()
+        )
+    }
+
+    fn http_partial_update_docs(op: TransformOperation) -> TransformOperation {
+        Self::http_if_match_docs(Self::http_detail_path_docs(op))
+            .summary(&Self::http_partial_update_summary())
+            .response::<200, Json<S::Output>>()
     }
 
     fn http_delete_summary() -> String {
@@ -89,17 +279,99 @@ where
     }
 
     fn http_delete_docs(op: TransformOperation) -> TransformOperation {
-        op.summary(&Self::http_delete_summary())
-            .response::<204, ()>()
+        let op = Self::http_if_match_docs(Self::http_detail_path_docs(op))
+            .summary(&Self::http_delete_summary())
+            .response::<204, ()>();
+        match Self::soft_delete() {
+            Some(soft_delete) => op.description(&format!(
+                "the instance is marked deleted by `{}` and can be restored",
+                soft_delete.column().as_str()
+            )),
+            None => op,
+        }
     }
 
-    fn http_delete_all_summary() -> String {
-        format!("delete all instances {}", Self::modle_schema_description())
+    fn http_restore_summary() -> String {
+        format!(
+            "restore a deleted instance {}",
+            Self::modle_schema_description()
+        )
     }
 
-    fn http_delete_all_docs(op: TransformOperation) -> TransformOperation {
-        op.summary(&Self::http_delete_all_summary())
-            .response::<204, ()>()
+    fn http_restore_docs(op: TransformOperation) -> TransformOperation {
+        Self::http_detail_path_docs(op)
+            .summary(&Self::http_restore_summary())
+            .description("only for the privileged callers, return 403 for the others")
+            .response::<200, Json<S::Output>>()
+    }
+
+    /// document `?include_deleted=true` if [`ModelViewExt::soft_delete`] is set
+    fn http_include_deleted_docs(op: TransformOperation) -> TransformOperation {
+        if Self::soft_delete().is_none() {
+            return op;
+        }
+        with_parameters(
+            op,
+            [query_parameter(
+                Self::include_deleted_param(),
+                "set `true` to include the deleted instances, only for the privileged callers",
+                InstanceType::Boolean,
+            )],
+        )
+    }
+
+    fn http_bulk_create_summary() -> String {
+        format!(
+            "create instances in a batch {}",
+            Self::modle_schema_description()
+        )
+    }
+
+    fn http_bulk_create_docs(op: TransformOperation) -> TransformOperation {
+        op.summary(&Self::http_bulk_create_summary())
+            .description(&format!(
+                "at most {} items in a batch, nothing is created if any item is invalid",
+                Self::max_batch_size()
+            ))
+            .input::<Json<Vec<S::Create>>>()
+            .response::<201, Json<Vec<BulkItemResult<S::Output>>>>()
+            .response::<400, Json<Vec<BulkItemResult<S::Output>>>>()
+    }
+
+    fn http_bulk_update_summary() -> String {
+        format!(
+            "partial update instances in a batch {}",
+            Self::modle_schema_description()
+        )
+    }
+
+    fn http_bulk_update_docs(op: TransformOperation) -> TransformOperation {
+        op.summary(&Self::http_bulk_update_summary())
+            .description(&format!(
+                "at most {} items in a batch, nothing is changed if any item is invalid or not found",
+                Self::max_batch_size()
+            ))
+            .response::<200, Json<Vec<BulkItemResult<S::Output>>>>()
+            .response::<400, Json<Vec<BulkItemResult<S::Output>>>>()
+    }
+
+    fn http_bulk_delete_summary() -> String {
+        format!(
+            "delete instances matched the filters {}",
+            Self::modle_schema_description()
+        )
+    }
+
+    fn http_bulk_delete_docs(op: TransformOperation) -> TransformOperation {
+        let op = op
+            .summary(&Self::http_bulk_delete_summary())
+            .description(&format!(
+                "at least one filter or search is required, \
+                nothing is deleted if more than {} instances are matched",
+                Self::max_batch_size()
+            ))
+            .response::<200, Json<Vec<BulkItemResult<S::Output>>>>();
+        Self::http_filter_docs(op)
     }
 
     fn http_list_summary() -> String {
@@ -107,8 +379,163 @@ where
     }
 
     fn http_list_docs(op: TransformOperation) -> TransformOperation {
-        op.summary(&Self::http_list_summary())
-            .response::<200, Json<Vec<<T::Entity as EntityTrait>::Model>>>()
+        let op = op.summary(&Self::http_list_summary());
+        let op = if Self::expand_relations().is_empty() {
+            Self::http_list_response_docs::<S::Output>(op)
+        } else {
+            Self::http_list_response_docs::<ExpandedOutput<Self, T, S>>(op)
+        };
+        Self::http_include_deleted_docs(Self::http_expand_docs(Self::http_list_filter_docs(op)))
+            .response_with::<304, (), _>(|res| res.description("the results are not modified"))
+    }
+
+    /// document the list response of `O` in the [`ModelViewExt::pagination_style`]
+    fn http_list_response_docs<O: JsonSchema>(op: TransformOperation) -> TransformOperation {
+        match Self::pagination_style() {
+            PaginationStyle::Plain => op.response::<200, Json<Vec<O>>>(),
+            PaginationStyle::Envelope => op.response::<200, Json<Page<O>>>(),
+            PaginationStyle::Headers => op.response_with::<200, Json<Vec<O>>, _>(|mut res| {
+                let headers = &mut res.inner().headers;
+                headers.insert(
+                    TOTAL_COUNT_HEADER.to_owned(),
+                    response_header("count of all the matched results", InstanceType::Integer),
+                );
+                headers.insert(
+                    header::LINK.to_string(),
+                    response_header(
+                        "RFC 8288 links with first, prev, next and last relations",
+                        InstanceType::String,
+                    ),
+                );
+                res
+            }),
+            PaginationStyle::Cursor => with_parameters(
+                op.response::<200, Json<CursorPage<O>>>(),
+                [query_parameter(
+                    Self::cursor_param(),
+                    "opaque cursor from `next_cursor` or `prev_cursor` of the last response",
+                    InstanceType::String,
+                )],
+            ),
+        }
+    }
+
+    /// document `?expand=` with all the relation paths which can be expanded
+    fn http_expand_docs(op: TransformOperation) -> TransformOperation {
+        let paths = Self::expand_relations()
+            .iter()
+            .flat_map(Expand::paths)
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            return op;
+        }
+        with_parameters(
+            op,
+            [query_parameter(
+                Self::expand_param(),
+                &format!(
+                    "comma separated relations to embed, at most {} levels, choices: {}",
+                    Self::max_expand_depth(),
+                    paths.join(", ")
+                ),
+                InstanceType::String,
+            )],
+        )
+    }
+
+    /// document page params and ordering as query parameters, then the filters
+    fn http_list_filter_docs(op: TransformOperation) -> TransformOperation {
+        let mut parameters = vec![
+            query_parameter(
+                Self::page_size_param(),
+                "page size, 0 means fetch all results",
+                InstanceType::Integer,
+            ),
+            query_parameter(
+                Self::page_num_param(),
+                "page number, start from 1",
+                InstanceType::Integer,
+            ),
+        ];
+        // the cursor pagination has its own ordering
+        if Self::pagination_style() != PaginationStyle::Cursor {
+            parameters.push(query_parameter(
+                Self::ordering_param(),
+                &format!(
+                    "comma separated ordering fields, prefix `-` for descending, choices: {}",
+                    Self::ordering_fields()
+                        .iter()
+                        .map(|column| column.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                InstanceType::String,
+            ));
+        }
+        Self::http_filter_docs(Self::http_fields_docs(with_parameters(op, parameters)))
+    }
+
+    /// document `?fields=` and `?exclude=` with the selectable columns in the output schema
+    fn http_fields_docs(op: TransformOperation) -> TransformOperation {
+        let choices = Self::selectable_fields()
+            .iter()
+            .map(|column| column.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let always = Self::output_columns(Self::always_included_fields())
+            .iter()
+            .map(|column| column.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        with_parameters(
+            op,
+            [
+                query_parameter(
+                    Self::fields_param(),
+                    &format!("comma separated fields to select only, choices: {choices}, always selected: {always}"),
+                    InstanceType::String,
+                ),
+                query_parameter(
+                    Self::exclude_param(),
+                    &format!("comma separated fields not to select, choices: {choices}"),
+                    InstanceType::String,
+                ),
+            ],
+        )
+    }
+
+    /// document search and every allowed filter `field__lookup` as query parameters
+    fn http_filter_docs(op: TransformOperation) -> TransformOperation {
+        let mut parameters = vec![];
+        let search_fields = Self::search_fields();
+        if !search_fields.is_empty() {
+            parameters.push(query_parameter(
+                Self::search_param(),
+                &format!(
+                    "search terms split by whitespace, match fields: {}",
+                    search_fields
+                        .iter()
+                        .map(|column| column.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                InstanceType::String,
+            ));
+        }
+        for column in Self::filter_fields() {
+            for lookup in Self::filter_lookups(&column) {
+                parameters.push(query_parameter(
+                    &lookup.query_key(&column),
+                    &format!(
+                        "filter `{}` by `{lookup}` with {}",
+                        column.as_str(),
+                        lookup.value_description()
+                    ),
+                    InstanceType::String,
+                ));
+            }
+        }
+        with_parameters(op, parameters)
     }
 
     fn http_create_summary() -> String {
@@ -116,25 +543,189 @@ where
     }
 
     fn http_create_docs(op: TransformOperation) -> TransformOperation {
-        op.summary(&Self::http_create_summary())
-            // .input::<Json<<T::Entity as EntityTrait>::Model>>()
-            .response::<201, ()>()
+        let op = op.summary(&Self::http_create_summary());
+        match Self::create_response() {
+            ResponseBody::Empty => op.response_with::<201, (), _>(with_location_header),
+            ResponseBody::Instance => {
+                op.response_with::<201, Json<S::Output>, _>(with_location_header)
+            }
+        }
     }
 
+    /// documented routers of the operates in [`ModelViewExt::actions`]
     fn model_api_router() -> ApiRouter {
-        ApiRouter::new()
-            .api_route(
-                "/:id",
-                get_with(Self::http_retrieve, Self::http_retrieve_docs)
-                    .put_with(Self::http_update, Self::http_update_docs)
-                    .delete_with(Self::http_delete, Self::http_delete_docs),
-            )
-            .api_route(
-                "/",
-                get_with(Self::http_list, Self::http_list_docs)
-                    .post_with(Self::http_create, Self::http_create_docs)
-                    .delete_with(Self::http_delete_all, Self::http_delete_all_docs),
-            )
+        let actions = Self::actions();
+        let restore = actions.method_router::<ApiMethodRouter>(&[(Action::Restore, |route| {
+            route.post_with(Self::http_restore, Self::http_restore_docs)
+        })]);
+        let detail = actions.method_router::<ApiMethodRouter>(&[
+            (Action::Retrieve, |route| {
+                route.get_with(Self::http_retrieve, Self::http_retrieve_docs)
+            }),
+            (Action::Update, |route| {
+                route.put_with(Self::http_update, Self::http_update_docs)
+            }),
+            (Action::PartialUpdate, |route| {
+                route.patch_with(Self::http_partial_update, Self::http_partial_update_docs)
+            }),
+            (Action::Delete, |route| {
+                route.delete_with(Self::http_delete, Self::http_delete_docs)
+            }),
+        ]);
+        let list = actions.method_router::<ApiMethodRouter>(&[
+            (Action::List, |route| {
+                route.get_with(Self::http_list, Self::http_list_docs)
+            }),
+            (Action::Create, |route| {
+                route.post_with(Self::http_create, Self::http_create_docs)
+            }),
+            (Action::BulkDelete, |route| {
+                route.delete_with(Self::http_bulk_delete, Self::http_bulk_delete_docs)
+            }),
+        ]);
+        let bulk = actions.method_router::<ApiMethodRouter>(&[
+            (Action::BulkCreate, |route| {
+                route.post_with(Self::http_bulk_create, Self::http_bulk_create_docs)
+            }),
+            (Action::BulkUpdate, |route| {
+                route.patch_with(Self::http_bulk_update, Self::http_bulk_update_docs)
+            }),
+        ]);
+        let mut routes = vec![
+            (Self::detail_path(), detail),
+            ("/".to_owned(), list),
+            (Self::bulk_path().to_owned(), bulk),
+        ];
+        if Self::soft_delete().is_some() {
+            routes.push((Self::restore_path(), restore));
+        }
+        api_routes(routes).merge(Self::extra_action_api_router())
+    }
+
+    fn http_extra_action_summary(name: &str) -> String {
+        format!("{name} {}", Self::modle_schema_description())
+    }
+
+    /// documented routes of [`ModelViewExt::extra_actions`], detail actions are documented with
+    /// the primary key path params and collection actions with the filters
+    fn extra_action_api_router() -> ApiRouter {
+        let detail_path = Self::detail_path();
+        let mut routes: BTreeMap<String, ApiMethodRouter> = BTreeMap::new();
+        for action in Self::extra_actions() {
+            let path = Self::extra_action_path(&action, &detail_path);
+            let method = action.method.clone();
+            let (name, docs) = (action.name, action.docs);
+            let method_router = routes.remove(&path).unwrap_or_default();
+            let method_router = match action.target {
+                ActionTarget::Detail(_) => api_method_with(
+                    method_router,
+                    &method,
+                    move |RequestParts(parts): RequestParts,
+                          Path(pk): Path<PrimaryKeyValue<T>>,
+                          Query(query): Query<serde_json::Value>,
+                          ActionBody(body): ActionBody| {
+                        Self::http_detail_action(action, parts, pk, query, body)
+                    },
+                    move |op| {
+                        let op = Self::http_detail_path_docs(op)
+                            .summary(&Self::http_extra_action_summary(name));
+                        match docs {
+                            Some(docs) => docs(op),
+                            None => op,
+                        }
+                    },
+                ),
+                ActionTarget::Collection(_) => api_method_with(
+                    method_router,
+                    &method,
+                    move |RequestParts(parts): RequestParts,
+                          Query(query): Query<serde_json::Value>,
+                          ActionBody(body): ActionBody| {
+                        Self::http_collection_action(action, parts, query, body)
+                    },
+                    move |op| {
+                        let op = Self::http_filter_docs(op)
+                            .summary(&Self::http_extra_action_summary(name));
+                        match docs {
+                            Some(docs) => docs(op),
+                            None => op,
+                        }
+                    },
+                ),
+            };
+            routes.insert(path, method_router);
+        }
+        routes
+            .into_iter()
+            .fold(ApiRouter::new(), |router, (path, method_router)| {
+                router.api_route(&path, method_router)
+            })
+    }
+
+    /// document the parent primary key path param of the nested routes
+    fn http_parent_path_docs<P>(op: TransformOperation) -> TransformOperation
+    where
+        P: ParentEntity,
+        P::Key: JsonSchema,
+    {
+        let mut gen = gen::SchemaGenerator::default();
+        let param = P::path_param();
+        let parameter = path_parameter(
+            &param,
+            &format!("primary key of the parent {}", P::default().table_name()),
+            gen.subschema_for::<P::Key>(),
+        );
+        with_parameters(op, [parameter])
+    }
+
+    /// api routers of the children scoped to a parent of entity `P` with docs,
+    /// see [`ModelViewExt::nested_router`]
+    fn nested_api_router<P, R>(parent_prefix: &str, name: &str, relation: R) -> ApiRouter
+    where
+        P: ParentEntity,
+        P::Key: JsonSchema,
+        R: RelationTrait + Clone + Send + Sync + 'static,
+    {
+        let actions = Self::actions();
+        let detail = actions.method_router::<ApiMethodRouter<R>>(&[
+            (Action::Retrieve, |route| {
+                route.get_with(Self::http_nested_retrieve::<P, R>, |op| {
+                    Self::http_retrieve_docs(Self::http_parent_path_docs::<P>(op))
+                })
+            }),
+            (Action::Update, |route| {
+                route.put_with(Self::http_nested_update::<P, R>, |op| {
+                    Self::http_update_docs(Self::http_parent_path_docs::<P>(op))
+                })
+            }),
+            (Action::PartialUpdate, |route| {
+                route.patch_with(Self::http_nested_partial_update::<P, R>, |op| {
+                    Self::http_partial_update_docs(Self::http_parent_path_docs::<P>(op))
+                })
+            }),
+            (Action::Delete, |route| {
+                route.delete_with(Self::http_nested_delete::<P, R>, |op| {
+                    Self::http_delete_docs(Self::http_parent_path_docs::<P>(op))
+                })
+            }),
+        ]);
+        let list = actions.method_router::<ApiMethodRouter<R>>(&[
+            (Action::List, |route| {
+                route.get_with(Self::http_nested_list::<P, R>, |op| {
+                    Self::http_list_docs(Self::http_parent_path_docs::<P>(op))
+                })
+            }),
+            (Action::Create, |route| {
+                route.post_with(Self::http_nested_create::<P, R>, |op| {
+                    Self::http_create_docs(Self::http_parent_path_docs::<P>(op))
+                })
+            }),
+        ]);
+        ApiRouter::new().nest_api_service(
+            &Self::nested_prefix::<P>(parent_prefix, name),
+            api_routes([(Self::detail_path(), detail), ("/".to_owned(), list)])
+                .with_state(relation),
+        )
     }
 
     async fn http_router_with_swagger(
@@ -146,15 +737,7 @@ where
     {
         let mut api = OpenApi::default();
 
-        awesome_operates::extract_all_files!(awesome_operates::embed::Asset);
-        awesome_operates::swagger::InitSwagger::new(
-            awesome_operates::embed::EXTRACT_SWAGGER_DIR_PATH,
-            "swagger-init.js",
-            "index.html",
-            "../api.json",
-        )
-        .build()
-        .await?;
+        init_swagger_files().await?;
         Ok(ApiRouter::new()
             .nest_api_service(nest_prefix, model_api_router)
             .nest_service("/swagger", ServeDir::new(Self::serve_dir_path()))
@@ -163,3 +746,285 @@ where
             .layer(Extension(Arc::new(api))))
     }
 }
+
+/// schema of [`ViewSchema::Output`] of view `V` with all the [`ModelViewExt::expand_relations`]
+pub struct ExpandedOutput<V: ?Sized, T, S>(PhantomData<(T, S)>, PhantomData<V>);
+
+impl<V, T, S> JsonSchema for ExpandedOutput<V, T, S>
+where
+    V: ModelViewExt<T, S> + ?Sized,
+    T: ActiveModelTrait + ActiveModelBehavior + Send + 'static + Sync,
+    S: ViewSchema<T> + 'static,
+    S::Output: JsonSchema,
+    <T::Entity as EntityTrait>::Model: IntoActiveModel<T> + Serialize + Sync,
+    for<'de> <T::Entity as EntityTrait>::Model: serde::de::Deserialize<'de>,
+    PrimaryKeyValue<T>: Serialize + Clone + Sync,
+    for<'de> PrimaryKeyValue<T>: serde::de::Deserialize<'de>,
+{
+    fn schema_name() -> String {
+        format!("{}Expanded", S::Output::schema_name())
+    }
+
+    fn json_schema(gen: &mut gen::SchemaGenerator) -> Schema {
+        expanded_schema::<S::Output, _>(gen, &V::expand_relations())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aide::openapi::{Parameter, ReferenceOr};
+
+    use crate::tests_cfg::{article, enrollment, note, tag};
+    use crate::views::{ActionSet, ExtraAction, SoftDelete};
+
+    #[derive(JsonSchema)]
+    struct TagView;
+
+    impl ModelViewExt<tag::ActiveModel> for TagView {
+        fn expand_relations() -> Vec<Expand<tag::Model>> {
+            vec![Expand::many::<article::Entity>("articles", vec![])]
+        }
+    }
+
+    impl SwaggerGeneratorExt<tag::ActiveModel> for TagView {}
+
+    #[derive(JsonSchema)]
+    struct EnrollmentView;
+
+    impl ModelViewExt<enrollment::ActiveModel> for EnrollmentView {}
+
+    impl SwaggerGeneratorExt<enrollment::ActiveModel> for EnrollmentView {}
+
+    struct TagSchema;
+
+    impl ViewSchema<tag::ActiveModel> for TagSchema {
+        type Create = tag::TagCreate;
+        type Update = tag::TagCreate;
+        type Output = tag::TagOutput;
+    }
+
+    #[derive(JsonSchema)]
+    struct TagSchemaView;
+
+    impl ModelViewExt<tag::ActiveModel, TagSchema> for TagSchemaView {}
+
+    impl SwaggerGeneratorExt<tag::ActiveModel, TagSchema> for TagSchemaView {}
+
+    fn path_parameters(api: &OpenApi, path: &str) -> Vec<(String, serde_json::Value)> {
+        let paths = api.paths.as_ref().unwrap();
+        let detail = paths.paths.get(path).unwrap().as_item().unwrap();
+        detail
+            .get
+            .as_ref()
+            .unwrap()
+            .parameters
+            .iter()
+            .filter_map(|p| match p {
+                ReferenceOr::Item(p @ Parameter::Path { .. }) => {
+                    let data = p.parameter_data_ref();
+                    Some((
+                        data.name.clone(),
+                        serde_json::json!(data.format)["schema"].clone(),
+                    ))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detail_path_param_use_primary_key_type() {
+        let mut api = OpenApi::default();
+        let _router = TagView::model_api_router().finish_api(&mut api);
+        let params = path_parameters(&api, "/{id}");
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].0, "id");
+        assert_eq!(params[0].1["type"], "string");
+
+        let mut api = OpenApi::default();
+        let _router = EnrollmentView::model_api_router().finish_api(&mut api);
+        let params = path_parameters(&api, "/{student_id}/{course_id}");
+        assert_eq!(
+            params
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            ["student_id", "course_id"]
+        );
+        assert_eq!(params[1].1["format"], "int32");
+    }
+
+    #[test]
+    fn separate_request_and_response_schemas() {
+        let mut api = OpenApi::default();
+        let _router = TagSchemaView::model_api_router().finish_api(&mut api);
+        let api = serde_json::json!(api);
+        let create = &api["paths"]["/"]["post"];
+        let request = create["requestBody"]["content"]["application/json"]["schema"].to_string();
+        let response =
+            create["responses"]["201"]["content"]["application/json"]["schema"].to_string();
+        assert!(request.contains("TagCreate"));
+        assert!(response.contains("TagOutput"));
+    }
+
+    #[derive(JsonSchema)]
+    struct ArticleView;
+
+    impl ModelViewExt<article::ActiveModel> for ArticleView {}
+
+    impl SwaggerGeneratorExt<article::ActiveModel> for ArticleView {}
+
+    #[test]
+    fn nested_routes_with_parent_path_param() {
+        let mut api = OpenApi::default();
+        let _router = ArticleView::nested_api_router::<tag::Entity, _>(
+            "/tag",
+            "articles",
+            article::Relation::Tag,
+        )
+        .finish_api(&mut api);
+        let params = path_parameters(&api, "/tag/{tag_id}/articles/{id}");
+        assert_eq!(
+            params
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            ["tag_id", "id"]
+        );
+        assert_eq!(params[0].1["type"], "string");
+        assert_eq!(params[1].1["format"], "int32");
+        let params = path_parameters(&api, "/tag/{tag_id}/articles/");
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn bearer_security_scheme_in_docs() {
+        let mut api = OpenApi::default();
+        let _router =
+            TagView::model_api_router().finish_api_with(&mut api, TagView::api_docs_head_config);
+        let api = serde_json::json!(api);
+        let scheme = &api["components"]["securitySchemes"][BEARER_AUTH];
+        assert_eq!(scheme["type"], "http");
+        assert_eq!(scheme["scheme"], "bearer");
+        assert_eq!(
+            api["security"],
+            serde_json::json!([{ BEARER_AUTH: [] }, {}])
+        );
+    }
+
+    #[test]
+    fn expanded_relations_in_docs() {
+        let mut api = OpenApi::default();
+        let _router = TagView::model_api_router().finish_api(&mut api);
+        let api = serde_json::json!(api);
+        let list = &api["paths"]["/"]["get"];
+        assert!(list["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|param| param["name"] == "expand"));
+        let response = &list["responses"]["200"]["content"]["application/json"]["schema"];
+        let schemas = serde_json::json!([response, api["components"]]).to_string();
+        assert!(schemas.contains("articles"));
+    }
+
+    #[derive(JsonSchema)]
+    struct NoteView;
+
+    impl ModelViewExt<note::ActiveModel> for NoteView {
+        fn soft_delete() -> Option<SoftDelete<note::Column>> {
+            Some(SoftDelete::Timestamp(note::Column::DeletedAt))
+        }
+    }
+
+    impl SwaggerGeneratorExt<note::ActiveModel> for NoteView {}
+
+    #[test]
+    fn soft_delete_routes_in_docs() {
+        let mut api = OpenApi::default();
+        let _router = NoteView::model_api_router().finish_api(&mut api);
+        let api = serde_json::json!(api);
+        assert!(api["paths"]["/{id}/restore"]["post"].is_object());
+        for operation in [&api["paths"]["/"]["get"], &api["paths"]["/{id}"]["get"]] {
+            assert!(operation["parameters"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|param| param["name"] == "include_deleted"));
+        }
+
+        let mut api = OpenApi::default();
+        let _router = ArticleView::model_api_router().finish_api(&mut api);
+        let api = serde_json::json!(api);
+        assert!(api["paths"].get("/{id}/restore").is_none());
+    }
+
+    #[derive(JsonSchema)]
+    struct ReadOnlyTagView;
+
+    impl ModelViewExt<tag::ActiveModel> for ReadOnlyTagView {
+        fn actions() -> ActionSet {
+            ActionSet::READ_ONLY
+        }
+    }
+
+    impl SwaggerGeneratorExt<tag::ActiveModel> for ReadOnlyTagView {}
+
+    #[test]
+    fn disabled_actions_not_in_docs() {
+        let mut api = OpenApi::default();
+        let _router = ReadOnlyTagView::model_api_router().finish_api(&mut api);
+        let paths = &serde_json::json!(api)["paths"];
+        assert!(paths["/"]["get"].is_object());
+        assert!(paths["/"].get("post").is_none());
+        assert!(paths["/"].get("delete").is_none());
+        assert!(paths["/{id}"]["get"].is_object());
+        assert!(paths["/{id}"].get("put").is_none());
+        assert!(paths.get("/bulk").is_none());
+    }
+
+    #[derive(JsonSchema)]
+    struct ActionTagView;
+
+    impl ModelViewExt<tag::ActiveModel> for ActionTagView {
+        fn filter_fields() -> Vec<tag::Column> {
+            vec![tag::Column::Title]
+        }
+
+        fn extra_actions() -> Vec<ExtraAction<tag::Entity>> {
+            vec![
+                ExtraAction::detail(Method::POST, "archive", |_, _| async {
+                    Ok(().into_response())
+                })
+                .docs(|op| op.description("archive the tag")),
+                ExtraAction::collection(Method::GET, "stats", |_, _| async {
+                    Ok(().into_response())
+                }),
+            ]
+        }
+    }
+
+    impl SwaggerGeneratorExt<tag::ActiveModel> for ActionTagView {}
+
+    #[test]
+    fn extra_actions_in_docs() {
+        let mut api = OpenApi::default();
+        let _router = ActionTagView::model_api_router().finish_api(&mut api);
+        let paths = &serde_json::json!(api)["paths"];
+        let archive = &paths["/{id}/archive"]["post"];
+        assert_eq!(archive["description"], "archive the tag");
+        assert!(archive["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|param| param["name"] == "id"));
+        let stats = &paths["/stats"]["get"];
+        assert!(stats["summary"].as_str().unwrap().starts_with("stats"));
+        assert!(stats["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|param| param["name"] == "title"));
+    }
+}
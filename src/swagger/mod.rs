@@ -1,3 +1,6 @@
 pub use generator::SwaggerGeneratorExt;
+pub use registry::{ApiResource, ApiRoot, ViewRegistry};
 
 mod generator;
+pub mod params;
+mod registry;
@@ -0,0 +1,132 @@
+use aide::openapi::{
+    Header, HeaderStyle, Parameter, ParameterData, ParameterSchemaOrContent, PathStyle, QueryStyle,
+    ReferenceOr, SchemaObject,
+};
+use aide::transform::{TransformOperation, TransformResponse};
+use schemars::schema::{InstanceType, Schema};
+
+fn simple_schema(instance_type: InstanceType) -> ParameterSchemaOrContent {
+    ParameterSchemaOrContent::Schema(SchemaObject {
+        json_schema: Schema::Object(schemars::schema::SchemaObject {
+            instance_type: Some(instance_type.into()),
+            ..Default::default()
+        }),
+        external_docs: None,
+        example: None,
+    })
+}
+
+/// build a not required query parameter with a simple schema type
+pub fn query_parameter(name: &str, description: &str, instance_type: InstanceType) -> Parameter {
+    Parameter::Query {
+        parameter_data: ParameterData {
+            name: name.to_owned(),
+            description: Some(description.to_owned()),
+            required: false,
+            deprecated: None,
+            format: simple_schema(instance_type),
+            example: None,
+            examples: Default::default(),
+            explode: None,
+            extensions: Default::default(),
+        },
+        allow_reserved: false,
+        style: QueryStyle::Form,
+        allow_empty_value: None,
+    }
+}
+
+/// build a required path parameter with the schema
+pub fn path_parameter(name: &str, description: &str, schema: Schema) -> Parameter {
+    Parameter::Path {
+        parameter_data: ParameterData {
+            name: name.to_owned(),
+            description: Some(description.to_owned()),
+            required: true,
+            deprecated: None,
+            format: ParameterSchemaOrContent::Schema(SchemaObject {
+                json_schema: schema,
+                external_docs: None,
+                example: None,
+            }),
+            example: None,
+            examples: Default::default(),
+            explode: None,
+            extensions: Default::default(),
+        },
+        style: PathStyle::Simple,
+    }
+}
+
+/// build a not required header parameter with a simple schema type
+pub fn header_parameter(name: &str, description: &str, instance_type: InstanceType) -> Parameter {
+    Parameter::Header {
+        parameter_data: ParameterData {
+            name: name.to_owned(),
+            description: Some(description.to_owned()),
+            required: false,
+            deprecated: None,
+            format: simple_schema(instance_type),
+            example: None,
+            examples: Default::default(),
+            explode: None,
+            extensions: Default::default(),
+        },
+        style: HeaderStyle::Simple,
+    }
+}
+
+/// append parameters into the operation, parameters with an existed name will be skipped
+pub fn with_parameters<I>(mut op: TransformOperation, parameters: I) -> TransformOperation
+where
+    I: IntoIterator<Item = Parameter>,
+{
+    let operation = op.inner_mut();
+    for parameter in parameters {
+        let exists = operation.parameters.iter().any(|p| match p {
+            ReferenceOr::Item(p) => {
+                p.parameter_data_ref().name == parameter.parameter_data_ref().name
+            }
+            ReferenceOr::Reference { .. } => false,
+        });
+        if !exists {
+            operation.parameters.push(ReferenceOr::Item(parameter));
+        }
+    }
+    op
+}
+
+/// build a response header with a simple schema type
+pub fn response_header(description: &str, instance_type: InstanceType) -> ReferenceOr<Header> {
+    ReferenceOr::Item(Header {
+        description: Some(description.to_owned()),
+        style: HeaderStyle::Simple,
+        required: false,
+        deprecated: None,
+        format: simple_schema(instance_type),
+        example: None,
+        examples: Default::default(),
+        extensions: Default::default(),
+    })
+}
+
+/// document the `Location` header of a created instance
+pub fn with_location_header<T>(mut res: TransformResponse<T>) -> TransformResponse<T> {
+    res.inner().headers.insert(
+        http::header::LOCATION.to_string(),
+        response_header("url of the created instance", InstanceType::String),
+    );
+    res
+}
+
+/// document the `ETag` header of the response
+pub fn with_etag_header<T>(mut res: TransformResponse<T>) -> TransformResponse<T> {
+    res.inner().headers.insert(
+        http::header::ETAG.to_string(),
+        response_header(
+            "version of the instance, send it in `If-Match` to update or delete",
+            InstanceType::String,
+        ),
+    );
+    res
+}
@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use aide::{
+    axum::{
+        routing::{get, get_with},
+        ApiRouter,
+    },
+    openapi::{OpenApi, Tag},
+    transform::TransformOpenApi,
+};
+use axum::{Extension, Json, Router};
+use schemars::JsonSchema;
+use sea_orm::{ActiveModelBehavior, ActiveModelTrait, EntityTrait, IntoActiveModel};
+use serde::Serialize;
+use snafu::ensure;
+use tower_http::services::ServeDir;
+
+use crate::error::{PrefixConflictSnafu, Result};
+use crate::swagger::generator::{default_docs_config, init_swagger_files, serve_openapi};
+use crate::swagger::SwaggerGeneratorExt;
+use crate::views::{PrimaryKeyValue, ViewSchema};
+
+const SWAGGER_PATH: &str = "/swagger";
+const OPENAPI_PATH: &str = "/api.json";
+
+/// a registered resource listed in the api root
+#[derive(Debug, Clone, JsonSchema, Serialize)]
+pub struct ApiResource {
+    /// name of the view, also the tag of its operations
+    pub name: String,
+    pub description: String,
+    /// route prefix of the view
+    pub url: String,
+}
+
+/// body of the api root
+#[derive(Debug, Clone, JsonSchema, Serialize)]
+pub struct ApiRoot {
+    pub resources: Vec<ApiResource>,
+    /// url of the swagger ui
+    pub docs: String,
+    /// url of the openapi document
+    pub openapi: String,
+}
+
+/// combine many views into one router with a single openapi document
+/// ```rust,no_run
+/// # use axum_restful::swagger::ViewRegistry;
+/// # async fn app() -> anyhow::Result<axum::Router> {
+/// let app = ViewRegistry::new()
+///     // .register::<StudentView, _, _>("/api/student")?
+///     // .register::<CourseView, _, _>("/api/course")?
+///     .into_router()
+///     .await?;
+/// # Ok(app)
+/// # }
+/// ```
+/// the router serves
+/// ```http
+/// // api root listing all the resources
+/// http://{ipaddress}:{port}/
+/// // swagger doc
+/// http://{ipaddress}:{port}/swagger/
+/// // openapi doc
+/// http://{ipaddress}:{port}/api.json
+/// ```
+pub struct ViewRegistry {
+    router: ApiRouter,
+    resources: Vec<ApiResource>,
+    docs_config: fn(TransformOpenApi) -> TransformOpenApi,
+}
+
+impl Default for ViewRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ViewRegistry {
+    pub fn new() -> Self {
+        Self {
+            router: ApiRouter::new(),
+            resources: vec![],
+            docs_config: default_docs_config,
+        }
+    }
+
+    /// change the head config of the openapi document, default is the title and the jwt bearer
+    /// security scheme
+    pub fn docs_config(mut self, docs_config: fn(TransformOpenApi) -> TransformOpenApi) -> Self {
+        self.docs_config = docs_config;
+        self
+    }
+
+    /// nest the [`SwaggerGeneratorExt::model_api_router`] of view `V` at the prefix,
+    /// a prefix equal to or nested in a registered prefix is a conflict
+    pub fn register<V, T, S>(mut self, prefix: &str) -> Result<Self>
+    where
+        V: SwaggerGeneratorExt<T, S> + Send + JsonSchema,
+        T: ActiveModelTrait + ActiveModelBehavior + Send + 'static + Sync,
+        S: ViewSchema<T> + 'static,
+        S::Create: JsonSchema,
+        S::Update: JsonSchema,
+        S::Output: JsonSchema,
+        <T::Entity as EntityTrait>::Model: IntoActiveModel<T> + Serialize + Sync + JsonSchema,
+        for<'de> <T::Entity as EntityTrait>::Model: serde::de::Deserialize<'de>,
+        PrimaryKeyValue<T>: Serialize + Clone + Sync + JsonSchema,
+        for<'de> PrimaryKeyValue<T>: serde::de::Deserialize<'de>,
+    {
+        let prefix = format!("/{}", prefix.trim_matches('/'));
+        for existing in ["/", SWAGGER_PATH, OPENAPI_PATH]
+            .into_iter()
+            .chain(self.resources.iter().map(|r| r.url.as_str()))
+        {
+            ensure!(
+                !prefix_overlaps(&prefix, existing),
+                PrefixConflictSnafu {
+                    prefix: prefix.clone(),
+                    existing,
+                }
+            );
+        }
+        let name = V::modle_name();
+        let router = V::model_api_router().with_path_items(|item| item.tag(&name));
+        self.router = self.router.nest_api_service(&prefix, router);
+        self.resources.push(ApiResource {
+            name,
+            description: V::modle_schema_description(),
+            url: prefix,
+        });
+        Ok(self)
+    }
+
+    /// build the router without extracting the swagger ui files, see [`Self::into_router`]
+    pub fn build(self) -> Router {
+        let root = Arc::new(ApiRoot {
+            resources: self.resources,
+            docs: format!("{SWAGGER_PATH}/"),
+            openapi: OPENAPI_PATH.to_owned(),
+        });
+        let tags = root
+            .resources
+            .iter()
+            .map(|resource| Tag {
+                name: resource.name.clone(),
+                description: Some(resource.description.clone()),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        let docs_config = self.docs_config;
+        let mut api = OpenApi::default();
+        let router = self
+            .router
+            .api_route(
+                "/",
+                get_with(
+                    move || async move { Json(ApiRoot::clone(&root)) },
+                    |op| {
+                        op.summary("api root")
+                            .description("list all the registered resources")
+                    },
+                ),
+            )
+            .nest_service(
+                SWAGGER_PATH,
+                ServeDir::new(awesome_operates::embed::EXTRACT_SWAGGER_DIR_PATH),
+            )
+            .route(OPENAPI_PATH, get(serve_openapi))
+            .finish_api_with(&mut api, |api| {
+                tags.into_iter()
+                    .fold(docs_config(api), |api, tag| api.tag(tag))
+            });
+        router.layer(Extension(Arc::new(api)))
+    }
+
+    /// extract the swagger ui files and build the router
+    pub async fn into_router(self) -> anyhow::Result<Router> {
+        init_swagger_files().await?;
+        Ok(self.build())
+    }
+}
+
+/// two prefixes are overlapped if they are equal or one is nested in the other
+fn prefix_overlaps(prefix: &str, existing: &str) -> bool {
+    let nested = |a: &str, b: &str| a.starts_with(b) && a[b.len()..].starts_with('/');
+    prefix == existing || nested(prefix, existing) || nested(existing, prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::StatusCode;
+
+    use crate::error::AppError;
+    use crate::test_helpers::TestClient;
+    use crate::tests_cfg::{note, tag};
+    use crate::views::ModelViewExt;
+
+    /// tag of the articles
+    #[derive(JsonSchema)]
+    struct TagView;
+
+    impl ModelViewExt<tag::ActiveModel> for TagView {}
+
+    impl SwaggerGeneratorExt<tag::ActiveModel> for TagView {}
+
+    #[derive(JsonSchema)]
+    struct NoteView;
+
+    impl ModelViewExt<note::ActiveModel> for NoteView {}
+
+    impl SwaggerGeneratorExt<note::ActiveModel> for NoteView {}
+
+    #[test]
+    fn conflicting_prefixes() {
+        let registry = ViewRegistry::new()
+            .register::<TagView, _, _>("/api/tag/")
+            .unwrap();
+        for prefix in ["api/tag", "/api/tag/archive", "/api", "/", "/swagger/tag"] {
+            let registry = ViewRegistry::new()
+                .register::<TagView, _, _>("/api/tag")
+                .unwrap();
+            let error = registry.register::<NoteView, _, _>(prefix).err().unwrap();
+            assert!(
+                matches!(error, AppError::PrefixConflict { .. }),
+                "{prefix}: {error}"
+            );
+        }
+        assert!(registry.register::<NoteView, _, _>("/api/tags").is_ok());
+    }
+
+    #[tokio::test]
+    async fn merged_docs_and_api_root() {
+        let router = ViewRegistry::new()
+            .register::<TagView, _, _>("/api/tag")
+            .unwrap()
+            .register::<NoteView, _, _>("/api/note")
+            .unwrap()
+            .build();
+        let client = TestClient::new(router);
+
+        let res = client.get("/").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let root = res.json::<serde_json::Value>().await;
+        assert_eq!(root["resources"][0]["name"], "tagview");
+        assert_eq!(root["resources"][0]["description"], "tag of the articles");
+        assert_eq!(root["resources"][1]["url"], "/api/note");
+        assert_eq!(root["openapi"], "/api.json");
+
+        let res = client.get("/api.json").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let api = res.json::<serde_json::Value>().await;
+        let paths = api["paths"].as_object().unwrap();
+        assert!(paths.contains_key("/"));
+        assert!(paths.contains_key("/api/tag/{id}"));
+        assert!(paths.contains_key("/api/note/"));
+        assert_eq!(paths["/api/tag/{id}"]["get"]["tags"][0], "tagview");
+        assert_eq!(paths["/api/note/"]["get"]["tags"][0], "noteview");
+        assert_eq!(api["tags"].as_array().unwrap().len(), 2);
+        assert!(api["components"]["securitySchemes"].is_object());
+    }
+}
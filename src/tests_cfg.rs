@@ -0,0 +1,150 @@
+//! entities used in tests
+
+pub mod tag {
+    use schemars::JsonSchema;
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    /// article tag
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, JsonSchema)]
+    #[sea_orm(table_name = "tag")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub slug: String,
+        pub title: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {
+        #[sea_orm(has_many = "super::article::Entity")]
+        Article,
+    }
+
+    impl Related<super::article::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Article.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    /// create a tag with a slug generated by database
+    #[derive(Deserialize, JsonSchema, DeriveIntoActiveModel)]
+    pub struct TagCreate {
+        pub title: String,
+    }
+
+    /// tag without title
+    #[derive(Serialize, JsonSchema)]
+    pub struct TagOutput {
+        pub slug: String,
+    }
+
+    impl From<Model> for TagOutput {
+        fn from(model: Model) -> Self {
+            Self { slug: model.slug }
+        }
+    }
+}
+
+pub mod enrollment {
+    use schemars::JsonSchema;
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    /// student enrollment of a course
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, JsonSchema)]
+    #[sea_orm(table_name = "enrollment")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub student_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub course_id: i32,
+        pub score: f64,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod article {
+    use schemars::JsonSchema;
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    /// article with a tag
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, JsonSchema)]
+    #[sea_orm(table_name = "article")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub title: String,
+        pub tag_slug: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::tag::Entity",
+            from = "Column::TagSlug",
+            to = "super::tag::Column::Slug"
+        )]
+        Tag,
+    }
+
+    impl Related<super::tag::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Tag.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    /// article without the tag
+    #[derive(Serialize, JsonSchema)]
+    pub struct ArticleOutput {
+        pub id: i32,
+        pub title: String,
+    }
+
+    impl From<Model> for ArticleOutput {
+        fn from(model: Model) -> Self {
+            Self {
+                id: model.id,
+                title: model.title,
+            }
+        }
+    }
+}
+
+pub mod note {
+    use schemars::JsonSchema;
+    use sea_orm::entity::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    /// note which is soft deleted
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, JsonSchema)]
+    #[sea_orm(table_name = "note")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+        pub content: String,
+        pub is_archived: bool,
+        pub version: i32,
+        #[schemars(with = "Option<String>")]
+        pub deleted_at: Option<DateTimeWithTimeZone>,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+
+    /// change the content only
+    #[derive(Deserialize, JsonSchema, DeriveIntoActiveModel)]
+    pub struct NoteUpdate {
+        pub content: String,
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use aide::transform::TransformOperation;
+use aide::OperationInput;
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Request};
+use axum::http::{request::Parts, Method};
+use axum::response::Response;
+use sea_orm::{EntityTrait, Select};
+use serde_json::Value;
+
+use crate::error::{AppError, InvalidBodySnafu, Result};
+use crate::views::permission::Action;
+use crate::views::validation::from_json_slice;
+
+/// an action with the route of its method and handler added to a method router
+pub(crate) type ActionRoute<R> = (Action, fn(R) -> R);
+
+/// the actions exposed by a view, the other actions are not routed and are left out of the docs,
+/// the other methods of a path answer 405 with the `Allow` header of the exposed ones,
+/// which is empty if no action of the path is exposed
+/// ```rust,ignore
+/// fn actions() -> ActionSet {
+///     ActionSet::EMPTY.with(Action::Create)
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ActionSet(u16);
+
+impl ActionSet {
+    /// no action
+    pub const EMPTY: Self = Self(0);
+    /// every action
+    pub const FULL: Self = Self::EMPTY
+        .with(Action::List)
+        .with(Action::Retrieve)
+        .with(Action::Create)
+        .with(Action::Update)
+        .with(Action::PartialUpdate)
+        .with(Action::Delete)
+        .with(Action::BulkCreate)
+        .with(Action::BulkUpdate)
+        .with(Action::BulkDelete)
+        .with(Action::Restore);
+    /// list and retrieve only
+    pub const READ_ONLY: Self = Self::EMPTY.with(Action::List).with(Action::Retrieve);
+    /// every action except deleting all the instances matched a filter with `DELETE /`
+    pub const NO_BULK_DELETE: Self = Self::FULL.without(Action::BulkDelete);
+
+    const fn bit(action: Action) -> u16 {
+        let index = match action {
+            Action::List => 0,
+            Action::Retrieve => 1,
+            Action::Create => 2,
+            Action::Update => 3,
+            Action::PartialUpdate => 4,
+            Action::Delete => 5,
+            Action::BulkCreate => 6,
+            Action::BulkUpdate => 7,
+            Action::BulkDelete => 8,
+            Action::Restore => 9,
+            // extra actions are exposed once they are registered
+            Action::Custom { .. } => return 0,
+        };
+        1 << index
+    }
+
+    pub const fn with(self, action: Action) -> Self {
+        Self(self.0 | Self::bit(action))
+    }
+
+    pub const fn without(self, action: Action) -> Self {
+        Self(self.0 & !Self::bit(action))
+    }
+
+    pub const fn contains(self, action: Action) -> bool {
+        self.0 & Self::bit(action) != 0
+    }
+
+    /// the method router of a path, each route of an exposed action adds the handler of its method,
+    /// `None` if no action of the path is exposed
+    pub(crate) fn method_router<R: Default>(self, routes: &[ActionRoute<R>]) -> Option<R> {
+        routes
+            .iter()
+            .filter(|(action, _)| self.contains(*action))
+            .fold(None, |router, (_, route)| {
+                Some(route(router.unwrap_or_default()))
+            })
+    }
+}
+
+impl Default for ActionSet {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+impl FromIterator<Action> for ActionSet {
+    fn from_iter<I: IntoIterator<Item = Action>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::EMPTY, Self::with)
+    }
+}
+
+/// the request of an extra action
+#[derive(Debug, Clone)]
+pub struct ActionRequest {
+    pub parts: Parts,
+    /// the query string as a json object
+    pub query: Value,
+    /// the json body, `null` if the body is empty
+    pub body: Value,
+}
+
+/// json body of an extra action, `null` if the body is empty, or 400 if the body is not a json
+#[derive(Debug, Clone)]
+pub struct ActionBody(pub Value);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequest<S> for ActionBody {
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self> {
+        let bytes = Bytes::from_request(req, state).await.map_err(|e| {
+            InvalidBodySnafu {
+                reason: e.body_text(),
+            }
+            .build()
+        })?;
+        if bytes.is_empty() {
+            return Ok(Self(Value::Null));
+        }
+        from_json_slice(&bytes).map(Self)
+    }
+}
+
+impl OperationInput for ActionBody {}
+
+type ActionFuture = Pin<Box<dyn Future<Output = Result<Response>> + Send>>;
+
+type ActionHandler<I> = Arc<dyn Fn(I, ActionRequest) -> ActionFuture + Send + Sync>;
+
+/// the target of an extra action
+#[derive(Clone)]
+pub enum ActionTarget<E: EntityTrait> {
+    /// an instance loaded by the primary key, routed like `/:id/graduate`
+    Detail(ActionHandler<E::Model>),
+    /// the instances matched the filter and search query, routed like `/stats`
+    Collection(ActionHandler<Select<E>>),
+}
+
+/// a custom endpoint of a view besides the crud actions, like `@action` of django rest framework,
+/// it is checked by the permissions as [`Action::Custom`]
+/// ```rust,ignore
+/// fn extra_actions() -> Vec<ExtraAction<student::Entity>> {
+///     vec![
+///         ExtraAction::detail(Method::POST, "graduate", |model, request| async move {
+///             graduate(model, request.body).await
+///         }),
+///         ExtraAction::collection(Method::GET, "stats", |select, _request| async move {
+///             let count = select.count(db).await.context(OperateDatabaseSnafu)?;
+///             Ok(Json(json!({"count": count})).into_response())
+///         }),
+///     ]
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ExtraAction<E: EntityTrait> {
+    pub method: Method,
+    /// the last segment of the path
+    pub name: &'static str,
+    pub target: ActionTarget<E>,
+    /// change the generated docs of the action, like the summary, input and responses
+    pub docs: Option<fn(TransformOperation) -> TransformOperation>,
+}
+
+impl<E: EntityTrait> ExtraAction<E> {
+    /// an action of an instance like `POST /api/student/:id/graduate`, the handler receives the loaded model
+    pub fn detail<F, Fut>(method: Method, name: &'static str, handler: F) -> Self
+    where
+        F: Fn(E::Model, ActionRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response>> + Send + 'static,
+    {
+        Self {
+            method,
+            name,
+            target: ActionTarget::Detail(Arc::new(move |model, request| {
+                Box::pin(handler(model, request))
+            })),
+            docs: None,
+        }
+    }
+
+    /// an action of the collection like `GET /api/student/stats`, the handler receives
+    /// the query filtered by the filter and search query params
+    pub fn collection<F, Fut>(method: Method, name: &'static str, handler: F) -> Self
+    where
+        F: Fn(Select<E>, ActionRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response>> + Send + 'static,
+    {
+        Self {
+            method,
+            name,
+            target: ActionTarget::Collection(Arc::new(move |select, request| {
+                Box::pin(handler(select, request))
+            })),
+            docs: None,
+        }
+    }
+
+    pub fn docs(mut self, docs: fn(TransformOperation) -> TransformOperation) -> Self {
+        self.docs = Some(docs);
+        self
+    }
+
+    /// the action checked by the permissions
+    pub fn action(&self) -> Action {
+        Action::Custom {
+            name: self.name,
+            safe: self.method == Method::GET || self.method == Method::HEAD,
+        }
+    }
+
+    /// path of the action under the view like `/:id/graduate` or `/stats`
+    pub fn path(&self, detail_path: &str) -> String {
+        match self.target {
+            ActionTarget::Detail(_) => format!("{detail_path}/{}", self.name),
+            ActionTarget::Collection(_) => format!("/{}", self.name),
+        }
+    }
+}
+
+impl<E: EntityTrait> Debug for ExtraAction<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let target = match self.target {
+            ActionTarget::Detail(_) => "detail",
+            ActionTarget::Collection(_) => "collection",
+        };
+        f.debug_struct("ExtraAction")
+            .field("method", &self.method)
+            .field("name", &self.name)
+            .field("target", &target)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_and_builder() {
+        assert!(ActionSet::FULL.contains(Action::BulkDelete));
+        assert!(ActionSet::READ_ONLY.contains(Action::Retrieve));
+        assert!(!ActionSet::READ_ONLY.contains(Action::Create));
+        assert!(!ActionSet::NO_BULK_DELETE.contains(Action::BulkDelete));
+        assert!(ActionSet::NO_BULK_DELETE.contains(Action::Delete));
+        let create_only = [Action::Create].into_iter().collect::<ActionSet>();
+        assert_eq!(create_only, ActionSet::EMPTY.with(Action::Create));
+        assert_eq!(
+            ActionSet::FULL,
+            ActionSet::NO_BULK_DELETE.with(Action::BulkDelete)
+        );
+    }
+}
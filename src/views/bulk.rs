@@ -0,0 +1,52 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// an item of the bulk update body, change `fields` of the instance with primary key `pk`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BulkUpdateItem<K> {
+    /// primary key of the instance, an array for a composite primary key
+    pub pk: K,
+    /// json object of the changed fields, every key must be a column name
+    pub fields: Map<String, Value>,
+}
+
+/// result of an item in a bulk operation, in the same order as the request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BulkItemResult<M> {
+    /// index of the item in the request
+    pub index: usize,
+    /// the saved or deleted instance, null if this or another item failed
+    pub instance: Option<M>,
+    /// why this item failed, null if this item is valid
+    pub error: Option<String>,
+}
+
+impl<M> BulkItemResult<M> {
+    pub fn ok(index: usize, instance: M) -> Self {
+        Self {
+            index,
+            instance: Some(instance),
+            error: None,
+        }
+    }
+
+    pub fn err(index: usize, error: impl ToString) -> Self {
+        Self {
+            index,
+            instance: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// drop the instances of a failed batch since nothing is saved, keep the errors only
+pub fn failed_results<M>(results: Vec<BulkItemResult<M>>) -> Vec<BulkItemResult<M>> {
+    results
+        .into_iter()
+        .map(|result| BulkItemResult {
+            instance: None,
+            ..result
+        })
+        .collect()
+}
@@ -0,0 +1,164 @@
+use std::str::FromStr;
+
+use sea_orm::prelude::{
+    ChronoDate, ChronoDateTime, ChronoDateTimeWithTimeZone, ChronoTime, Decimal, Uuid,
+};
+use sea_orm::sea_query::{sea_value_to_json_value, ColumnType, Nullable};
+use sea_orm::{ColumnTrait, Value};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::error::{InvalidFieldSnafu, Result};
+
+/// deserialize a json value into `Option<V>` and then trans into a sea-orm value,
+/// json `null` will become a typed `NULL`
+fn typed_value<V>(value: &JsonValue) -> std::result::Result<Value, String>
+where
+    V: DeserializeOwned + Into<Value> + Nullable,
+{
+    serde_json::from_value::<Option<V>>(value.clone())
+        .map(Into::into)
+        .map_err(|e| e.to_string())
+}
+
+fn decimal_value(value: &JsonValue) -> std::result::Result<Value, String> {
+    let decimal = match value {
+        JsonValue::Null => None,
+        JsonValue::Number(n) => Some(Decimal::from_str(&n.to_string()).map_err(|e| e.to_string())?),
+        JsonValue::String(s) => Some(Decimal::from_str(s).map_err(|e| e.to_string())?),
+        _ => return Err(format!("invalid type: {value}, expected a decimal")),
+    };
+    Ok(decimal.into())
+}
+
+/// trans a json value into a sea-orm value with the type of `column`
+/// return `AppError::InvalidField` if the json value not match the column type
+pub fn json_to_value<C: ColumnTrait>(column: &C, value: &JsonValue) -> Result<Value> {
+    let converted = match column.def().get_column_type() {
+        ColumnType::Char(_)
+        | ColumnType::String(_)
+        | ColumnType::Text
+        | ColumnType::Custom(_)
+        | ColumnType::Enum { .. } => typed_value::<String>(value),
+        ColumnType::TinyInteger => typed_value::<i8>(value),
+        ColumnType::SmallInteger => typed_value::<i16>(value),
+        ColumnType::Integer => typed_value::<i32>(value),
+        ColumnType::BigInteger => typed_value::<i64>(value),
+        ColumnType::TinyUnsigned => typed_value::<u8>(value),
+        ColumnType::SmallUnsigned => typed_value::<u16>(value),
+        ColumnType::Unsigned => typed_value::<u32>(value),
+        ColumnType::BigUnsigned => typed_value::<u64>(value),
+        ColumnType::Float => typed_value::<f32>(value),
+        ColumnType::Double => typed_value::<f64>(value),
+        ColumnType::Decimal(_) | ColumnType::Money(_) => decimal_value(value),
+        ColumnType::DateTime | ColumnType::Timestamp => typed_value::<ChronoDateTime>(value),
+        ColumnType::TimestampWithTimeZone => typed_value::<ChronoDateTimeWithTimeZone>(value),
+        ColumnType::Time => typed_value::<ChronoTime>(value),
+        ColumnType::Date => typed_value::<ChronoDate>(value),
+        ColumnType::Boolean => typed_value::<bool>(value),
+        ColumnType::Uuid => typed_value::<Uuid>(value),
+        ColumnType::Binary(_) | ColumnType::VarBinary(_) => typed_value::<Vec<u8>>(value),
+        ColumnType::Json | ColumnType::JsonBinary => Ok(match value {
+            JsonValue::Null => Value::Json(None),
+            _ => Value::Json(Some(Box::new(value.clone()))),
+        }),
+        col_type => Err(format!("unsupported column type {col_type:?}")),
+    };
+    converted.map_err(|reason| {
+        InvalidFieldSnafu {
+            field: column.as_str(),
+            reason,
+        }
+        .build()
+    })
+}
+
+fn serialized<V: Serialize>(value: &V) -> JsonValue {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// trans a sea-orm value into a json value which [`json_to_value`] reads back with the column type,
+/// `NULL` becomes `null`
+pub fn value_to_json(value: &Value) -> JsonValue {
+    match value {
+        Value::ChronoDate(v) => serialized(v),
+        Value::ChronoTime(v) => serialized(v),
+        Value::ChronoDateTime(v) => serialized(v),
+        Value::ChronoDateTimeUtc(v) => serialized(v),
+        Value::ChronoDateTimeLocal(v) => serialized(v),
+        Value::ChronoDateTimeWithTimeZone(v) => serialized(v),
+        Value::Decimal(v) => serialized(&v.as_ref().map(|v| v.to_string())),
+        Value::Bytes(v) => serialized(v),
+        other => sea_value_to_json_value(other),
+    }
+}
+
+/// trans a raw query string value into a sea-orm value with the type of `column`
+/// text like columns keep the raw string, others parse it as json first
+pub fn query_to_value<C: ColumnTrait>(column: &C, raw: &str) -> Result<Value> {
+    let value = match column.def().get_column_type() {
+        ColumnType::Char(_)
+        | ColumnType::String(_)
+        | ColumnType::Text
+        | ColumnType::Custom(_)
+        | ColumnType::Enum { .. }
+        | ColumnType::Decimal(_)
+        | ColumnType::Money(_)
+        | ColumnType::DateTime
+        | ColumnType::Timestamp
+        | ColumnType::TimestampWithTimeZone
+        | ColumnType::Time
+        | ColumnType::Date
+        | ColumnType::Uuid => JsonValue::String(raw.to_owned()),
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| JsonValue::String(raw.to_owned())),
+    };
+    json_to_value(column, &value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::tests_cfg::cake;
+
+    #[test]
+    fn json_into_column_value() {
+        assert_eq!(
+            json_to_value(&cake::Column::Id, &serde_json::json!(3)).unwrap(),
+            Value::Int(Some(3))
+        );
+        assert_eq!(
+            json_to_value(&cake::Column::Name, &serde_json::json!("cheese")).unwrap(),
+            Value::String(Some(Box::new("cheese".to_owned())))
+        );
+        assert_eq!(
+            json_to_value(&cake::Column::Name, &serde_json::Value::Null).unwrap(),
+            Value::String(None)
+        );
+        assert!(json_to_value(&cake::Column::Id, &serde_json::json!("three")).is_err());
+        assert!(json_to_value(&cake::Column::Id, &serde_json::json!(1.5)).is_err());
+    }
+
+    #[test]
+    fn value_into_json_and_back() {
+        let id = value_to_json(&Value::Int(Some(3)));
+        assert_eq!(
+            json_to_value(&cake::Column::Id, &id).unwrap(),
+            Value::Int(Some(3))
+        );
+        let name = value_to_json(&Value::String(None));
+        assert_eq!(
+            json_to_value(&cake::Column::Name, &name).unwrap(),
+            Value::String(None)
+        );
+
+        let time =
+            ChronoDateTime::parse_from_str("2024-01-02 03:04:05", "%Y-%m-%d %H:%M:%S").unwrap();
+        let json = value_to_json(&time.into());
+        assert_eq!(json, serde_json::json!("2024-01-02T03:04:05"));
+        assert_eq!(typed_value::<ChronoDateTime>(&json).unwrap(), time.into());
+        let decimal = Decimal::new(125, 2);
+        let json = value_to_json(&decimal.into());
+        assert_eq!(json, serde_json::json!("1.25"));
+        assert_eq!(decimal_value(&json).unwrap(), decimal.into());
+    }
+}
@@ -0,0 +1,354 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use schemars::JsonSchema;
+use sea_orm::sea_query::ValueTuple;
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, FromQueryResult, ModelTrait, Order,
+    QueryFilter, QueryOrder, QuerySelect, Select,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use snafu::{OptionExt, ResultExt};
+
+use crate::error::{InternalServerSnafu, InvalidFieldSnafu, OperateDatabaseSnafu, Result};
+use crate::views::convert::{json_to_value, value_to_json};
+
+/// cursor paginated results
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct CursorPage<M> {
+    /// opaque cursor for the next page, null if this is the last page
+    pub next_cursor: Option<String>,
+    /// opaque cursor for the previous page, null if this is the first page
+    pub prev_cursor: Option<String>,
+    pub results: Vec<M>,
+}
+
+/// decoded content of an opaque cursor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CursorToken {
+    /// values of the ordering columns in the boundary row
+    pub values: Vec<JsonValue>,
+    /// fetch rows before the boundary row if true, or after it
+    pub backward: bool,
+}
+
+/// read the values of the ordering columns in a row for the cursors
+pub type CursorValues<M, C> = fn(&M, &[C]) -> Vec<JsonValue>;
+
+/// values of the ordering columns in the model, read with [`ModelTrait::get`]
+pub fn model_values<M: ModelTrait>(
+    model: &M,
+    columns: &[<M::Entity as EntityTrait>::Column],
+) -> Vec<JsonValue> {
+    columns
+        .iter()
+        .map(|column| value_to_json(&model.get(*column)))
+        .collect()
+}
+
+/// values of the ordering columns in a row selected with `into_json`, keyed by the column names
+pub fn row_values<C: ColumnTrait>(row: &JsonValue, columns: &[C]) -> Vec<JsonValue> {
+    columns
+        .iter()
+        .map(|column| row.get(column.as_str()).cloned().unwrap_or_default())
+        .collect()
+}
+
+impl CursorToken {
+    /// encode into an url safe base64 string
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// decode an url safe base64 string, return 400 if the cursor is invalid
+    pub fn decode(cursor: &str) -> Result<Self> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .context(InvalidFieldSnafu {
+                field: "cursor",
+                reason: "invalid cursor",
+            })
+    }
+
+    /// trans json values into sea-orm values with the type of `columns`
+    pub fn value_tuple<C: ColumnTrait>(&self, columns: &[C]) -> Result<ValueTuple> {
+        if self.values.len() != columns.len() {
+            return InvalidFieldSnafu {
+                field: "cursor",
+                reason: "cursor not match the ordering",
+            }
+            .fail();
+        }
+        Ok(ValueTuple::Many(
+            columns
+                .iter()
+                .zip(self.values.iter())
+                .map(|(column, value)| json_to_value(column, value))
+                .collect::<Result<Vec<_>>>()?,
+        ))
+    }
+}
+
+/// keyset condition of the rows after the boundary `values` in the `ordering`,
+/// like `a > 1 OR (a = 1 AND b < 2)` for the ordering `a, -b`
+fn keyset_condition<C: ColumnTrait>(ordering: &[(C, Order)], values: ValueTuple) -> Condition {
+    let values = values.into_iter().collect::<Vec<_>>();
+    let mut condition = Condition::any();
+    for (index, ((column, order), value)) in ordering.iter().zip(&values).enumerate() {
+        let after = match order {
+            Order::Desc => column.lt(value.clone()),
+            _ => column.gt(value.clone()),
+        };
+        let equal = ordering
+            .iter()
+            .zip(&values)
+            .take(index)
+            .fold(Condition::all(), |all, ((column, _), value)| {
+                all.add(column.eq(value.clone()))
+            });
+        condition = condition.add(equal.add(after));
+    }
+    condition
+}
+
+fn reverse(order: &Order) -> Order {
+    match order {
+        Order::Desc => Order::Asc,
+        _ => Order::Desc,
+    }
+}
+
+/// fetch a page of `M` with keyset pagination in the `ordering`, every column has its own direction,
+/// `M` can be the entity model with [`model_values`] or a json value with [`row_values`]
+/// when only some columns are selected, `values` reads the ordering columns of a row for the cursors
+/// one more row is fetched to check whether there is a next or previous page
+/// return 500 if an ordering column is nullable, since a keyset comparison never matches `NULL`
+pub async fn fetch_cursor_page<E, C, D, M>(
+    mut select: Select<E>,
+    db: &D,
+    ordering: &[(C, Order)],
+    cursor: Option<&str>,
+    page_size: u64,
+    values: CursorValues<M, C>,
+) -> Result<CursorPage<M>>
+where
+    E: EntityTrait<Column = C>,
+    M: Serialize + FromQueryResult + Sync,
+    C: ColumnTrait,
+    D: ConnectionTrait,
+{
+    if let Some((column, _)) = ordering.iter().find(|(column, _)| column.def().is_null()) {
+        tracing::error!(
+            "cursor ordering column `{}` is nullable, the rows with NULL would be skipped",
+            column.as_str()
+        );
+        return InternalServerSnafu.fail();
+    }
+    let token = cursor.map(CursorToken::decode).transpose()?;
+    let columns = ordering
+        .iter()
+        .map(|(column, _)| *column)
+        .collect::<Vec<_>>();
+    let backward = token.as_ref().is_some_and(|token| token.backward);
+    // fetch backward in the reversed ordering, then reverse the results
+    let ordering = ordering
+        .iter()
+        .map(|(column, order)| {
+            (
+                *column,
+                if backward {
+                    reverse(order)
+                } else {
+                    order.clone()
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    if let Some(token) = &token {
+        select = select.filter(keyset_condition(&ordering, token.value_tuple(&columns)?));
+    }
+    for (column, order) in &ordering {
+        select = select.order_by(*column, order.clone());
+    }
+    let mut results = select
+        .limit(page_size.saturating_add(1))
+        .into_model::<M>()
+        .all(db)
+        .await
+        .context(OperateDatabaseSnafu)?;
+    let has_more = results.len() as u64 > page_size;
+    if has_more {
+        results.pop();
+    }
+    if backward {
+        results.reverse();
+    }
+    let (has_next, has_prev) = if backward {
+        (true, has_more)
+    } else {
+        (has_more, token.is_some())
+    };
+    let next_cursor = results.last().filter(|_| has_next).map(|model| {
+        CursorToken {
+            values: values(model, &columns),
+            backward: false,
+        }
+        .encode()
+    });
+    let prev_cursor = results.first().filter(|_| has_prev).map(|model| {
+        CursorToken {
+            values: values(model, &columns),
+            backward: true,
+        }
+        .encode()
+    });
+    Ok(CursorPage {
+        next_cursor,
+        prev_cursor,
+        results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::tests_cfg::{cake, fruit};
+    use sea_orm::{DatabaseBackend, MockDatabase, Transaction};
+
+    fn cake_model(id: i32) -> cake::Model {
+        cake::Model {
+            id,
+            name: format!("cake {id}"),
+        }
+    }
+
+    #[test]
+    fn cursor_token_round_trip() {
+        let token = CursorToken {
+            values: model_values(&cake_model(3), &[cake::Column::Id]),
+            backward: true,
+        };
+        assert_eq!(token.values, vec![serde_json::json!(3)]);
+        assert_eq!(
+            row_values(&serde_json::json!({"id": 3}), &[cake::Column::Id]),
+            token.values
+        );
+        assert_eq!(CursorToken::decode(&token.encode()).unwrap(), token);
+        assert_eq!(
+            token.value_tuple(&[cake::Column::Id]).unwrap(),
+            ValueTuple::Many(vec![sea_orm::Value::Int(Some(3))])
+        );
+        assert!(CursorToken::decode("not a cursor").is_err());
+        assert!(token
+            .value_tuple(&[cake::Column::Id, cake::Column::Name])
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn fetch_next_page_by_cursor() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![cake_model(4), cake_model(3), cake_model(2)]])
+            .into_connection();
+        let cursor = CursorToken {
+            values: vec![serde_json::json!(5)],
+            backward: false,
+        }
+        .encode();
+        let page = fetch_cursor_page::<_, _, _, cake::Model>(
+            cake::Entity::find(),
+            &db,
+            &[(cake::Column::Id, Order::Desc)],
+            Some(&cursor),
+            2,
+            model_values,
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.results, vec![cake_model(4), cake_model(3)]);
+        assert_eq!(
+            CursorToken::decode(&page.next_cursor.unwrap())
+                .unwrap()
+                .values,
+            vec![serde_json::json!(3)]
+        );
+        assert!(
+            CursorToken::decode(&page.prev_cursor.unwrap())
+                .unwrap()
+                .backward
+        );
+        assert_eq!(
+            db.into_transaction_log(),
+            [Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE "cake"."id" < $1 ORDER BY "cake"."id" DESC LIMIT $2"#,
+                [5i32.into(), 3u64.into()]
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_prev_page_with_mixed_directions() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![cake_model(7), cake_model(6)]])
+            .into_connection();
+        let cursor = CursorToken {
+            values: vec![serde_json::json!("cake 5"), serde_json::json!(5)],
+            backward: true,
+        }
+        .encode();
+        let page = fetch_cursor_page::<_, _, _, cake::Model>(
+            cake::Entity::find(),
+            &db,
+            &[
+                (cake::Column::Name, Order::Asc),
+                (cake::Column::Id, Order::Desc),
+            ],
+            Some(&cursor),
+            2,
+            model_values,
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.results, vec![cake_model(6), cake_model(7)]);
+        assert!(page.prev_cursor.is_none());
+        assert!(page.next_cursor.is_some());
+        assert_eq!(
+            db.into_transaction_log(),
+            [Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE "cake"."name" < $1 OR ("cake"."name" = $2 AND "cake"."id" > $3) ORDER BY "cake"."name" DESC, "cake"."id" ASC LIMIT $4"#,
+                ["cake 5".into(), "cake 5".into(), 5i32.into(), 3u64.into()]
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_nullable_ordering_and_huge_page_size() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<cake::Model>::new()])
+            .into_connection();
+        let page = fetch_cursor_page::<_, _, _, fruit::Model>(
+            fruit::Entity::find(),
+            &db,
+            &[(fruit::Column::CakeId, Order::Asc)],
+            None,
+            2,
+            model_values,
+        )
+        .await;
+        assert!(page.is_err());
+        let page = fetch_cursor_page::<_, _, _, cake::Model>(
+            cake::Entity::find(),
+            &db,
+            &[(cake::Column::Id, Order::Asc)],
+            None,
+            u64::MAX,
+            model_values,
+        )
+        .await
+        .unwrap();
+        assert!(page.results.is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::http::{header, HeaderMap};
+use sea_orm::sea_query::SimpleExpr;
+use sea_orm::{ColumnTrait, ModelTrait, Value};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use snafu::ensure;
+
+use crate::error::{InternalServerSnafu, PreconditionFailedSnafu, Result};
+
+/// how the `ETag` of an instance is computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ETagStyle<C> {
+    /// hash of the serialized output of the model
+    Hash,
+    /// an integer version column, bumped by every write with `WHERE version = ?`,
+    /// so a write based on a stale version never overwrites another one
+    Version(C),
+}
+
+impl<C: ColumnTrait> ETagStyle<C> {
+    /// the quoted strong `ETag` of the model like `"3"`, `output` is the serialized form of the model
+    /// in the responses which is hashed
+    pub fn etag<M, O>(&self, model: &M, output: impl FnOnce(&M) -> O) -> Result<String>
+    where
+        M: ModelTrait,
+        M::Entity: sea_orm::EntityTrait<Column = C>,
+        O: Serialize,
+    {
+        match self {
+            Self::Hash => {
+                let bytes = serde_json::to_vec(&output(model)).map_err(|e| {
+                    tracing::error!("serialize model to compute etag failed: {e}");
+                    InternalServerSnafu.build()
+                })?;
+                Ok(hash_etag(&bytes))
+            }
+            Self::Version(column) => Ok(format!(
+                "\"{}\"",
+                version_number(column, &model.get(*column))?
+            )),
+        }
+    }
+}
+
+/// the quoted strong `ETag` of the bytes
+pub fn hash_etag(bytes: &[u8]) -> String {
+    let tag = Sha256::digest(bytes)[..16]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("\"{tag}\"")
+}
+
+fn version_number<C: ColumnTrait>(column: &C, value: &Value) -> Result<i64> {
+    let number = match value {
+        Value::TinyInt(v) => v.map(i64::from),
+        Value::SmallInt(v) => v.map(i64::from),
+        Value::Int(v) => v.map(i64::from),
+        Value::BigInt(v) => *v,
+        Value::TinyUnsigned(v) => v.map(i64::from),
+        Value::SmallUnsigned(v) => v.map(i64::from),
+        Value::Unsigned(v) => v.map(i64::from),
+        Value::BigUnsigned(v) => v.and_then(|v| i64::try_from(v).ok()),
+        _ => {
+            tracing::error!(
+                "version column `{}` is not an integer: {value:?}",
+                column.as_str()
+            );
+            return InternalServerSnafu.fail();
+        }
+    };
+    Ok(number.unwrap_or_default())
+}
+
+/// the value of the version column after a write, `NULL` becomes 1
+pub fn next_version<C: ColumnTrait>(column: &C, value: &Value) -> Result<Value> {
+    let next = version_number(column, value)? + 1;
+    let value = match value {
+        Value::TinyInt(_) => i8::try_from(next).ok().map(Value::from),
+        Value::SmallInt(_) => i16::try_from(next).ok().map(Value::from),
+        Value::Int(_) => i32::try_from(next).ok().map(Value::from),
+        Value::TinyUnsigned(_) => u8::try_from(next).ok().map(Value::from),
+        Value::SmallUnsigned(_) => u16::try_from(next).ok().map(Value::from),
+        Value::Unsigned(_) => u32::try_from(next).ok().map(Value::from),
+        Value::BigUnsigned(_) => u64::try_from(next).ok().map(Value::from),
+        _ => Some(Value::from(next)),
+    };
+    value.ok_or_else(|| {
+        tracing::error!("version column `{}` overflows", column.as_str());
+        InternalServerSnafu.build()
+    })
+}
+
+/// condition of the row still at the `version` read before a write, a `NULL` version is matched
+/// with `IS NULL` since `= NULL` never matches
+pub fn version_condition<C: ColumnTrait>(column: &C, version: Value) -> SimpleExpr {
+    let is_null = matches!(
+        version,
+        Value::TinyInt(None)
+            | Value::SmallInt(None)
+            | Value::Int(None)
+            | Value::BigInt(None)
+            | Value::TinyUnsigned(None)
+            | Value::SmallUnsigned(None)
+            | Value::Unsigned(None)
+            | Value::BigUnsigned(None)
+    );
+    if is_null {
+        column.is_null()
+    } else {
+        column.eq(version)
+    }
+}
+
+/// check the `If-Match` header with the strong comparison, a request without the header always passes
+/// return 412 if none of the etags matches
+pub fn check_if_match(headers: &HeaderMap, etag: &str) -> Result<()> {
+    let mut values = headers.get_all(header::IF_MATCH).iter().peekable();
+    if values.peek().is_none() {
+        return Ok(());
+    }
+    let matched = values
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag);
+    ensure!(
+        matched,
+        PreconditionFailedSnafu {
+            reason: "the instance was changed, fetch it again to get the current `ETag`",
+        }
+    );
+    Ok(())
+}
+
+/// the time of a timestamp column value, or an integer column value of unix seconds
+pub fn modified_time(value: &Value) -> Option<SystemTime> {
+    match value {
+        Value::ChronoDateTime(Some(v)) => Some(v.and_utc().into()),
+        Value::ChronoDateTimeUtc(Some(v)) => Some((**v).into()),
+        Value::ChronoDateTimeLocal(Some(v)) => Some((**v).into()),
+        Value::ChronoDateTimeWithTimeZone(Some(v)) => Some((**v).into()),
+        Value::BigInt(Some(v)) => u64::try_from(*v)
+            .ok()
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+        _ => None,
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// whether a safe request can be answered with `304 Not Modified`,
+/// `If-None-Match` is compared weakly and takes precedence over `If-Modified-Since`
+pub fn is_not_modified(headers: &HeaderMap, etag: &str, last_modified: Option<SystemTime>) -> bool {
+    let mut values = headers.get_all(header::IF_NONE_MATCH).iter().peekable();
+    if values.peek().is_some() {
+        let etag = etag.trim_start_matches("W/");
+        return values
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+    }
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| httpdate::parse_http_date(value).ok());
+    match (last_modified, since) {
+        // http dates have a precision of seconds
+        (Some(last_modified), Some(since)) => unix_seconds(last_modified) <= unix_seconds(since),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests_cfg::note;
+    use axum::http::HeaderValue;
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QuerySelect, QueryTrait};
+
+    #[test]
+    fn etag_and_if_match() {
+        let note = note::Model {
+            id: 1,
+            content: "todo".to_owned(),
+            is_archived: false,
+            version: 3,
+            deleted_at: None,
+        };
+        let content = |note: &note::Model| note.content.clone();
+        let hash = ETagStyle::Hash.etag(&note, content).unwrap();
+        assert_eq!(hash, hash_etag(br#""todo""#));
+        // only the output is hashed, so a column left out of it never changes the etag
+        let archived = note::Model {
+            is_archived: true,
+            ..note.clone()
+        };
+        assert_eq!(ETagStyle::Hash.etag(&archived, content).unwrap(), hash);
+        assert_ne!(ETagStyle::Hash.etag(&archived, Clone::clone).unwrap(), hash);
+        let version = ETagStyle::Version(note::Column::Version);
+        assert_eq!(version.etag(&note, content).unwrap(), r#""3""#);
+        assert_eq!(
+            next_version(&note::Column::Version, &Value::Int(Some(3))).unwrap(),
+            Value::Int(Some(4))
+        );
+        assert!(next_version(&note::Column::Content, &Value::from("a")).is_err());
+        assert_eq!(
+            next_version(&note::Column::Version, &Value::Int(None)).unwrap(),
+            Value::Int(Some(1))
+        );
+        let sql = |version| {
+            note::Entity::find()
+                .select_only()
+                .column(note::Column::Id)
+                .filter(version_condition(&note::Column::Version, version))
+                .build(DbBackend::Postgres)
+                .to_string()
+        };
+        assert!(sql(Value::Int(Some(3))).ends_with(r#"WHERE "note"."version" = 3"#));
+        assert!(sql(Value::Int(None)).ends_with(r#"WHERE "note"."version" IS NULL"#));
+
+        let mut headers = HeaderMap::new();
+        assert!(check_if_match(&headers, r#""3""#).is_ok());
+        headers.insert(header::IF_MATCH, HeaderValue::from_static(r#""2", "3""#));
+        assert!(check_if_match(&headers, r#""3""#).is_ok());
+        assert!(check_if_match(&headers, r#""4""#).is_err());
+        headers.insert(header::IF_MATCH, HeaderValue::from_static(r#"W/"4""#));
+        assert!(check_if_match(&headers, r#""4""#).is_err());
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("*"));
+        assert!(check_if_match(&headers, r#""4""#).is_ok());
+    }
+
+    #[test]
+    fn not_modified_with_etag_or_time() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut headers = HeaderMap::new();
+        assert!(!is_not_modified(&headers, r#""a""#, Some(time)));
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(&httpdate::fmt_http_date(time)).unwrap(),
+        );
+        assert!(is_not_modified(&headers, r#""a""#, Some(time)));
+        assert!(!is_not_modified(
+            &headers,
+            r#""a""#,
+            Some(time + Duration::from_secs(1))
+        ));
+        assert!(!is_not_modified(&headers, r#""a""#, None));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static(r#"W/"a""#));
+        assert!(is_not_modified(&headers, r#""a""#, None));
+        assert!(!is_not_modified(&headers, r#""b""#, Some(time)));
+
+        assert_eq!(
+            modified_time(&Value::BigInt(Some(1_700_000_000))),
+            Some(time)
+        );
+        assert_eq!(modified_time(&Value::Int(Some(1))), None);
+    }
+}
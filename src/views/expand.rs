@@ -0,0 +1,582 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{
+    ArrayValidation, InstanceType, ObjectValidation, Schema, SchemaObject, SubschemaValidation,
+};
+use schemars::JsonSchema;
+use sea_orm::{
+    Condition, DatabaseConnection, EntityTrait, LoaderTrait, ModelTrait, QueryFilter, Related,
+};
+use serde::Serialize;
+use serde_json::Value;
+use snafu::{ensure, ResultExt};
+
+use crate::error::{InvalidFieldSnafu, OperateDatabaseSnafu, Result};
+
+/// separator between nested relations in expand query, like `courses.teacher`
+pub const EXPAND_SEPARATOR: char = '.';
+
+/// a relation of model `M` which can be embedded into responses with `?expand=name`
+///
+/// related models are loaded in batch with the sea-orm loaders, one query per relation
+/// like `find_with_related` but still work with paginated results,
+/// the `*_as` constructors embed the related models as an output type `O` to hide some columns,
+/// and [`Expand::filter`] hides the related models like the soft deleted ones
+/// ```rust,ignore
+/// fn expand_relations() -> Vec<Expand<student::Model>> {
+///     vec![
+///         Expand::one_as::<teacher::Entity, TeacherOutput>("teacher", vec![]),
+///         Expand::many_via::<course::Entity, enrollment::Entity>(
+///             "courses",
+///             vec![Expand::one::<teacher::Entity>("teacher", vec![])],
+///         )
+///         .filter(SoftDelete::Timestamp(course::Column::DeletedAt).alive_condition()),
+///     ]
+/// }
+/// ```
+pub struct Expand<M> {
+    name: &'static str,
+    loader: Box<dyn RelationLoader<M>>,
+    condition: Condition,
+}
+
+impl<M> Expand<M>
+where
+    M: ModelTrait + Sync,
+{
+    /// a belongs to or has one relation, embedded as an object or null,
+    /// `children` are the relations of the related model which can be expanded with `?expand=name.child`
+    pub fn one<R>(name: &'static str, children: Vec<Expand<R::Model>>) -> Self
+    where
+        R: EntityTrait,
+        R::Model: Serialize + JsonSchema + Send + Sync,
+        M::Entity: Related<R>,
+    {
+        Self::one_as::<R, R::Model>(name, children)
+    }
+
+    /// a belongs to or has one relation, embedded as the output `O` of the related model
+    pub fn one_as<R, O>(name: &'static str, children: Vec<Expand<R::Model>>) -> Self
+    where
+        R: EntityTrait,
+        R::Model: Sync,
+        O: From<R::Model> + Serialize + JsonSchema + 'static,
+        M::Entity: Related<R>,
+    {
+        Self::new::<R, O>(name, Kind::One, children)
+    }
+
+    /// a has many relation, embedded as an array
+    pub fn many<R>(name: &'static str, children: Vec<Expand<R::Model>>) -> Self
+    where
+        R: EntityTrait,
+        R::Model: Serialize + JsonSchema + Send + Sync,
+        M::Entity: Related<R>,
+    {
+        Self::many_as::<R, R::Model>(name, children)
+    }
+
+    /// a has many relation, embedded as an array of the output `O` of the related models
+    pub fn many_as<R, O>(name: &'static str, children: Vec<Expand<R::Model>>) -> Self
+    where
+        R: EntityTrait,
+        R::Model: Sync,
+        O: From<R::Model> + Serialize + JsonSchema + 'static,
+        M::Entity: Related<R>,
+    {
+        Self::new::<R, O>(name, Kind::Many, children)
+    }
+
+    /// a many to many relation through the junction entity `V`, embedded as an array
+    pub fn many_via<R, V>(name: &'static str, children: Vec<Expand<R::Model>>) -> Self
+    where
+        R: EntityTrait,
+        R::Model: Serialize + JsonSchema + Send + Sync,
+        V: EntityTrait,
+        V::Model: Send + Sync,
+        M::Entity: Related<R>,
+    {
+        Self::many_via_as::<R, V, R::Model>(name, children)
+    }
+
+    /// a many to many relation through the junction entity `V`,
+    /// embedded as an array of the output `O` of the related models
+    pub fn many_via_as<R, V, O>(name: &'static str, children: Vec<Expand<R::Model>>) -> Self
+    where
+        R: EntityTrait,
+        R::Model: Sync,
+        V: EntityTrait,
+        V::Model: Send + Sync,
+        O: From<R::Model> + Serialize + JsonSchema + 'static,
+        M::Entity: Related<R>,
+    {
+        Self {
+            name,
+            loader: Box::new(ManyViaLoader::<R, V, O> {
+                children,
+                via: PhantomData,
+            }),
+            condition: Condition::all(),
+        }
+    }
+
+    fn new<R, O>(name: &'static str, kind: Kind, children: Vec<Expand<R::Model>>) -> Self
+    where
+        R: EntityTrait,
+        R::Model: Sync,
+        O: From<R::Model> + Serialize + JsonSchema + 'static,
+        M::Entity: Related<R>,
+    {
+        Self {
+            name,
+            loader: Box::new(RelatedLoader::<R, O> {
+                kind,
+                children,
+                entity: PhantomData,
+            }),
+            condition: Condition::all(),
+        }
+    }
+}
+
+impl<M> Expand<M> {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// only embed the related models matched the condition,
+    /// like the [`crate::views::SoftDelete::alive_condition`] of a soft deleted entity
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.condition = condition;
+        self
+    }
+
+    /// names of this relation and all the nested relations like `courses.teacher`
+    pub fn paths(&self) -> Vec<String> {
+        let mut paths = vec![self.name.to_owned()];
+        for child in self.loader.child_paths() {
+            paths.push(format!("{}{EXPAND_SEPARATOR}{child}", self.name));
+        }
+        paths
+    }
+}
+
+/// a parsed expand query item, like `courses` with child `teacher` for `courses.teacher`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandTree {
+    pub name: String,
+    pub children: Vec<ExpandTree>,
+}
+
+impl ExpandTree {
+    fn insert(trees: &mut Vec<ExpandTree>, path: &[&str]) {
+        let Some((name, rest)) = path.split_first() else {
+            return;
+        };
+        let index = match trees.iter().position(|tree| tree.name == *name) {
+            Some(index) => index,
+            None => {
+                trees.push(ExpandTree {
+                    name: (*name).to_owned(),
+                    children: vec![],
+                });
+                trees.len() - 1
+            }
+        };
+        Self::insert(&mut trees[index].children, rest);
+    }
+}
+
+/// parse expand query like `teacher,courses.teacher` into trees
+/// every relation must be declared in `expansions` and not deeper than `max_depth`, or return 400
+pub fn parse_expand<M>(
+    raw: &str,
+    expansions: &[Expand<M>],
+    max_depth: usize,
+) -> Result<Vec<ExpandTree>> {
+    let mut trees = vec![];
+    for item in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let path = item.split(EXPAND_SEPARATOR).collect::<Vec<_>>();
+        ensure!(
+            path.len() <= max_depth,
+            InvalidFieldSnafu {
+                field: item,
+                reason: format!("expand is limited to {max_depth} levels"),
+            }
+        );
+        ExpandTree::insert(&mut trees, &path);
+    }
+    validate(&trees, expansions)?;
+    Ok(trees)
+}
+
+fn validate<M>(trees: &[ExpandTree], expansions: &[Expand<M>]) -> Result<()> {
+    for tree in trees {
+        let Some(expand) = expansions.iter().find(|expand| expand.name == tree.name) else {
+            return InvalidFieldSnafu {
+                field: &tree.name,
+                reason: "relation can not be expanded",
+            }
+            .fail();
+        };
+        expand.loader.validate(&tree.children)?;
+    }
+    Ok(())
+}
+
+/// embed the related models in `trees` into `values`, which are the json of `models` in the same order
+pub async fn expand_models<M: Sync>(
+    models: &[M],
+    mut values: Vec<Value>,
+    db: &DatabaseConnection,
+    trees: &[ExpandTree],
+    expansions: &[Expand<M>],
+) -> Result<Vec<Value>> {
+    for tree in trees {
+        let Some(expand) = expansions.iter().find(|expand| expand.name == tree.name) else {
+            continue;
+        };
+        let related = expand
+            .loader
+            .load(models, db, &tree.children, &expand.condition)
+            .await?;
+        for (value, related) in values.iter_mut().zip(related) {
+            if let Value::Object(object) = value {
+                object.insert(tree.name.clone(), related);
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// schema of `O` with every relation in `expansions` as an optional property
+pub fn expanded_schema<O: JsonSchema, M>(
+    gen: &mut SchemaGenerator,
+    expansions: &[Expand<M>],
+) -> Schema {
+    let base = gen.subschema_for::<O>();
+    if expansions.is_empty() {
+        return base;
+    }
+    let properties = expansions
+        .iter()
+        .map(|expand| (expand.name.to_owned(), expand.loader.schema(gen)))
+        .collect();
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            all_of: Some(vec![
+                base,
+                SchemaObject {
+                    instance_type: Some(InstanceType::Object.into()),
+                    object: Some(Box::new(ObjectValidation {
+                        properties,
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                }
+                .into(),
+            ]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    One,
+    Many,
+}
+
+#[async_trait]
+trait RelationLoader<M>: Send + Sync {
+    /// json of the related models matched the condition for every model in the same order
+    async fn load(
+        &self,
+        models: &[M],
+        db: &DatabaseConnection,
+        children: &[ExpandTree],
+        condition: &Condition,
+    ) -> Result<Vec<Value>>;
+
+    fn validate(&self, children: &[ExpandTree]) -> Result<()>;
+
+    fn child_paths(&self) -> Vec<String>;
+
+    fn schema(&self, gen: &mut SchemaGenerator) -> Schema;
+}
+
+struct RelatedLoader<R: EntityTrait, O> {
+    kind: Kind,
+    children: Vec<Expand<R::Model>>,
+    entity: PhantomData<fn() -> (R, O)>,
+}
+
+struct ManyViaLoader<R: EntityTrait, V, O> {
+    children: Vec<Expand<R::Model>>,
+    via: PhantomData<fn() -> (V, O)>,
+}
+
+fn child_paths<R>(children: &[Expand<R>]) -> Vec<String> {
+    children.iter().flat_map(Expand::paths).collect()
+}
+
+/// expand the related models of all the groups at once as the output `O` and regroup them
+async fn expand_groups<R, O>(
+    groups: Vec<Vec<R>>,
+    db: &DatabaseConnection,
+    trees: &[ExpandTree],
+    expansions: &[Expand<R>],
+) -> Result<Vec<Vec<Value>>>
+where
+    R: Clone + Sync,
+    O: From<R> + Serialize,
+{
+    let counts = groups.iter().map(Vec::len).collect::<Vec<_>>();
+    let related = groups.into_iter().flatten().collect::<Vec<_>>();
+    let values = related
+        .iter()
+        .map(|model| serde_json::json!(O::from(model.clone())))
+        .collect();
+    let mut values = expand_models(&related, values, db, trees, expansions)
+        .await?
+        .into_iter();
+    Ok(counts
+        .into_iter()
+        .map(|count| values.by_ref().take(count).collect())
+        .collect())
+}
+
+fn nullable(schema: Schema) -> Schema {
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(vec![
+                schema,
+                SchemaObject {
+                    instance_type: Some(InstanceType::Null.into()),
+                    ..Default::default()
+                }
+                .into(),
+            ]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn array(schema: Schema) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Array.into()),
+        array: Some(Box::new(ArrayValidation {
+            items: Some(schema.into()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+#[async_trait]
+impl<M, R, O> RelationLoader<M> for RelatedLoader<R, O>
+where
+    M: ModelTrait + Sync,
+    R: EntityTrait,
+    R::Model: Sync,
+    O: From<R::Model> + Serialize + JsonSchema + 'static,
+    M::Entity: Related<R>,
+{
+    async fn load(
+        &self,
+        models: &[M],
+        db: &DatabaseConnection,
+        children: &[ExpandTree],
+        condition: &Condition,
+    ) -> Result<Vec<Value>> {
+        let groups = match self.kind {
+            Kind::One => models
+                .load_one(R::find().filter(condition.clone()), db)
+                .await
+                .context(OperateDatabaseSnafu)?
+                .into_iter()
+                .map(|related| related.into_iter().collect())
+                .collect(),
+            Kind::Many => models
+                .load_many(R::find().filter(condition.clone()), db)
+                .await
+                .context(OperateDatabaseSnafu)?,
+        };
+        let groups = expand_groups::<_, O>(groups, db, children, &self.children).await?;
+        Ok(groups
+            .into_iter()
+            .map(|group| match self.kind {
+                Kind::One => group.into_iter().next().unwrap_or(Value::Null),
+                Kind::Many => Value::Array(group),
+            })
+            .collect())
+    }
+
+    fn validate(&self, children: &[ExpandTree]) -> Result<()> {
+        validate(children, &self.children)
+    }
+
+    fn child_paths(&self) -> Vec<String> {
+        child_paths(&self.children)
+    }
+
+    fn schema(&self, gen: &mut SchemaGenerator) -> Schema {
+        let schema = expanded_schema::<O, _>(gen, &self.children);
+        match self.kind {
+            Kind::One => nullable(schema),
+            Kind::Many => array(schema),
+        }
+    }
+}
+
+#[async_trait]
+impl<M, R, V, O> RelationLoader<M> for ManyViaLoader<R, V, O>
+where
+    M: ModelTrait + Sync,
+    R: EntityTrait,
+    R::Model: Sync,
+    O: From<R::Model> + Serialize + JsonSchema + 'static,
+    V: EntityTrait,
+    V::Model: Send + Sync,
+    M::Entity: Related<R>,
+{
+    async fn load(
+        &self,
+        models: &[M],
+        db: &DatabaseConnection,
+        children: &[ExpandTree],
+        condition: &Condition,
+    ) -> Result<Vec<Value>> {
+        let groups = models
+            .load_many_to_many(R::find().filter(condition.clone()), V::default(), db)
+            .await
+            .context(OperateDatabaseSnafu)?;
+        let groups = expand_groups::<_, O>(groups, db, children, &self.children).await?;
+        Ok(groups.into_iter().map(Value::Array).collect())
+    }
+
+    fn validate(&self, children: &[ExpandTree]) -> Result<()> {
+        validate(children, &self.children)
+    }
+
+    fn child_paths(&self) -> Vec<String> {
+        child_paths(&self.children)
+    }
+
+    fn schema(&self, gen: &mut SchemaGenerator) -> Schema {
+        array(expanded_schema::<O, _>(gen, &self.children))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests_cfg::{article, tag};
+    use sea_orm::{ColumnTrait, DatabaseBackend, MockDatabase};
+
+    fn tag_expansions() -> Vec<Expand<tag::Model>> {
+        vec![Expand::many_as::<article::Entity, article::ArticleOutput>(
+            "articles",
+            vec![Expand::one::<tag::Entity>("tag", vec![])],
+        )]
+    }
+
+    fn article(id: i32) -> article::Model {
+        article::Model {
+            id,
+            title: format!("article {id}"),
+            tag_slug: "rust".to_owned(),
+        }
+    }
+
+    #[test]
+    fn query_into_expand_trees() {
+        let expansions = tag_expansions();
+        assert_eq!(
+            expansions
+                .iter()
+                .flat_map(Expand::paths)
+                .collect::<Vec<_>>(),
+            ["articles", "articles.tag"]
+        );
+        assert_eq!(
+            parse_expand("articles.tag, articles", &expansions, 2).unwrap(),
+            [ExpandTree {
+                name: "articles".to_owned(),
+                children: vec![ExpandTree {
+                    name: "tag".to_owned(),
+                    children: vec![],
+                }],
+            }]
+        );
+        assert!(parse_expand("articles.tag", &expansions, 1).is_err());
+        assert!(parse_expand("unknown", &expansions, 2).is_err());
+        assert!(parse_expand("articles.unknown", &expansions, 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn embed_related_models() {
+        let rust = tag::Model {
+            slug: "rust".to_owned(),
+            title: "Rust".to_owned(),
+        };
+        let go = tag::Model {
+            slug: "go".to_owned(),
+            title: "Go".to_owned(),
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[article(1), article(2)]])
+            .into_connection();
+        let expansions = tag_expansions();
+        let trees = parse_expand("articles", &expansions, 2).unwrap();
+        let models = [rust.clone(), go.clone()];
+        let values = models
+            .iter()
+            .map(|model| serde_json::json!(model))
+            .collect();
+        let values = expand_models(&models, values, &db, &trees, &expansions)
+            .await
+            .unwrap();
+        assert_eq!(
+            values[0]["articles"],
+            serde_json::json!([
+                article::ArticleOutput::from(article(1)),
+                article::ArticleOutput::from(article(2)),
+            ])
+        );
+        assert!(values[0]["articles"][0].get("tag_slug").is_none());
+        assert_eq!(values[1]["articles"], serde_json::json!([]));
+        assert_eq!(values[1]["title"], "Go");
+    }
+
+    #[tokio::test]
+    async fn filter_related_models() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[article(1)]])
+            .into_connection();
+        let expansions = vec![Expand::many::<article::Entity>("articles", vec![])
+            .filter(Condition::all().add(article::Column::Title.ne("draft")))];
+        let trees = parse_expand("articles", &expansions, 2).unwrap();
+        let models = [tag::Model {
+            slug: "rust".to_owned(),
+            title: "Rust".to_owned(),
+        }];
+        let values = vec![serde_json::json!(models[0])];
+        expand_models(&models, values, &db, &trees, &expansions)
+            .await
+            .unwrap();
+        let log = format!("{:?}", db.into_transaction_log());
+        assert!(log.contains(r#"\"article\".\"title\" <> $1"#), "{log}");
+    }
+}
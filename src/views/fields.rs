@@ -0,0 +1,101 @@
+use sea_orm::ColumnTrait;
+
+use crate::error::{InvalidFieldSnafu, Result};
+
+/// parse comma separated fields query like `id,name`
+/// every field must be a column name and one of `allowed`, or return 400
+pub fn parse_fields<C: ColumnTrait>(raw: &str, allowed: &[C]) -> Result<Vec<C>> {
+    let mut columns = vec![];
+    for name in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let Ok(column) = C::from_str(name) else {
+            return InvalidFieldSnafu {
+                field: name,
+                reason: "unknown field",
+            }
+            .fail();
+        };
+        if !allowed.iter().any(|c| c.as_str() == column.as_str()) {
+            return InvalidFieldSnafu {
+                field: name,
+                reason: "field is not allowed to be selected",
+            }
+            .fail();
+        }
+        columns.push(column);
+    }
+    Ok(columns)
+}
+
+/// columns to select with `?fields=` and `?exclude=`, return `None` if both are absent
+/// columns in `always` are selected even if they are not in `fields` or are in `exclude`,
+/// the selected columns keep the order of the entity columns
+pub fn projection<C: ColumnTrait>(
+    fields: Option<&str>,
+    exclude: Option<&str>,
+    allowed: &[C],
+    always: &[C],
+) -> Result<Option<Vec<C>>> {
+    if fields.is_none() && exclude.is_none() {
+        return Ok(None);
+    }
+    let fields = fields.map(|raw| parse_fields(raw, allowed)).transpose()?;
+    let exclude = exclude
+        .map(|raw| parse_fields(raw, allowed))
+        .transpose()?
+        .unwrap_or_default();
+    let contains =
+        |columns: &[C], column: &C| columns.iter().any(|c| c.as_str() == column.as_str());
+    let columns = C::iter()
+        .filter(|column| {
+            contains(always, column)
+                || (contains(allowed, column)
+                    && fields
+                        .as_ref()
+                        .is_none_or(|fields| contains(fields, column))
+                    && !contains(&exclude, column))
+        })
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        return InvalidFieldSnafu {
+            field: "fields",
+            reason: "no field is selected",
+        }
+        .fail();
+    }
+    Ok(Some(columns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::tests_cfg::fruit;
+    use sea_orm::IdenStatic;
+
+    #[test]
+    fn query_into_projection() {
+        let allowed = [fruit::Column::Name, fruit::Column::CakeId];
+        let always = [fruit::Column::Id];
+        assert!(projection(None, None, &allowed, &always).unwrap().is_none());
+        let columns = projection(Some("cake_id, name"), None, &allowed, &always)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            columns.iter().map(|c| c.as_str()).collect::<Vec<_>>(),
+            ["id", "name", "cake_id"]
+        );
+        let columns = projection(None, Some("name"), &allowed, &always)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            columns.iter().map(|c| c.as_str()).collect::<Vec<_>>(),
+            ["id", "cake_id"]
+        );
+        assert!(projection(Some("unknown"), None, &allowed, &always).is_err());
+        assert!(projection(Some("id"), None, &allowed[..1], &[]).is_err());
+        assert!(projection(None, Some("name,cake_id"), &allowed, &[]).is_err());
+    }
+}
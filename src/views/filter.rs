@@ -0,0 +1,319 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sea_orm::sea_query::{ColumnType, Expr, Func, LikeExpr, SimpleExpr};
+use sea_orm::{ColumnTrait, Condition};
+use serde_json::Value;
+use snafu::ensure;
+
+use crate::error::{InvalidFieldSnafu, Result};
+use crate::views::convert::query_to_value;
+
+/// separator between a field and a lookup in query key, like `age__gte`
+pub const LOOKUP_SEPARATOR: &str = "__";
+
+/// escape character of the like patterns built from query values
+const LIKE_ESCAPE: char = '\\';
+
+/// django-rest-framework style field lookups used by query string filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    Exact,
+    Iexact,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Contains,
+    Icontains,
+    StartsWith,
+    EndsWith,
+    In,
+    Range,
+    IsNull,
+}
+
+impl Lookup {
+    pub const ALL: [Lookup; 13] = [
+        Lookup::Exact,
+        Lookup::Iexact,
+        Lookup::Gt,
+        Lookup::Gte,
+        Lookup::Lt,
+        Lookup::Lte,
+        Lookup::Contains,
+        Lookup::Icontains,
+        Lookup::StartsWith,
+        Lookup::EndsWith,
+        Lookup::In,
+        Lookup::Range,
+        Lookup::IsNull,
+    ];
+
+    /// lookups of the number, date and time columns
+    pub const ORDERED: [Lookup; 8] = [
+        Lookup::Exact,
+        Lookup::Gt,
+        Lookup::Gte,
+        Lookup::Lt,
+        Lookup::Lte,
+        Lookup::In,
+        Lookup::Range,
+        Lookup::IsNull,
+    ];
+
+    /// lookups of the other columns like boolean, uuid or enum
+    pub const EQUALITY: [Lookup; 3] = [Lookup::Exact, Lookup::In, Lookup::IsNull];
+
+    /// default lookups of the column by its type, the text lookups like `icontains`
+    /// are only for the text columns
+    pub fn defaults<C: ColumnTrait>(column: &C) -> &'static [Lookup] {
+        match column.def().get_column_type() {
+            ColumnType::Char(_) | ColumnType::String(_) | ColumnType::Text => &Lookup::ALL,
+            ColumnType::TinyInteger
+            | ColumnType::SmallInteger
+            | ColumnType::Integer
+            | ColumnType::BigInteger
+            | ColumnType::TinyUnsigned
+            | ColumnType::SmallUnsigned
+            | ColumnType::Unsigned
+            | ColumnType::BigUnsigned
+            | ColumnType::Float
+            | ColumnType::Double
+            | ColumnType::Decimal(_)
+            | ColumnType::Money(_)
+            | ColumnType::DateTime
+            | ColumnType::Timestamp
+            | ColumnType::TimestampWithTimeZone
+            | ColumnType::Time
+            | ColumnType::Date
+            | ColumnType::Year(_) => &Lookup::ORDERED,
+            _ => &Lookup::EQUALITY,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lookup::Exact => "exact",
+            Lookup::Iexact => "iexact",
+            Lookup::Gt => "gt",
+            Lookup::Gte => "gte",
+            Lookup::Lt => "lt",
+            Lookup::Lte => "lte",
+            Lookup::Contains => "contains",
+            Lookup::Icontains => "icontains",
+            Lookup::StartsWith => "startswith",
+            Lookup::EndsWith => "endswith",
+            Lookup::In => "in",
+            Lookup::Range => "range",
+            Lookup::IsNull => "isnull",
+        }
+    }
+
+    /// query key for the column with this lookup, `exact` is the bare column name
+    pub fn query_key<C: ColumnTrait>(&self, column: &C) -> String {
+        match self {
+            Lookup::Exact => column.as_str().to_owned(),
+            _ => format!("{}{LOOKUP_SEPARATOR}{}", column.as_str(), self.as_str()),
+        }
+    }
+
+    /// description of the query value for docs
+    pub fn value_description(&self) -> &'static str {
+        match self {
+            Lookup::In => "comma separated values",
+            Lookup::Range => "two comma separated values as `start,end`",
+            Lookup::IsNull => "`true` or `false`",
+            _ => "a single value",
+        }
+    }
+
+    /// build the sql condition for the column with a raw query value
+    pub fn condition<C: ColumnTrait>(&self, column: C, raw: &str) -> Result<SimpleExpr> {
+        let expr = match self {
+            Lookup::Exact => column.eq(query_to_value(&column, raw)?),
+            Lookup::Iexact => lower(column).eq(raw.to_lowercase()),
+            Lookup::Gt => column.gt(query_to_value(&column, raw)?),
+            Lookup::Gte => column.gte(query_to_value(&column, raw)?),
+            Lookup::Lt => column.lt(query_to_value(&column, raw)?),
+            Lookup::Lte => column.lte(query_to_value(&column, raw)?),
+            Lookup::Contains => Expr::col((column.entity_name(), column)).like(like("%", raw, "%")),
+            Lookup::Icontains => lower(column).like(like("%", &raw.to_lowercase(), "%")),
+            Lookup::StartsWith => {
+                Expr::col((column.entity_name(), column)).like(like("", raw, "%"))
+            }
+            Lookup::EndsWith => Expr::col((column.entity_name(), column)).like(like("%", raw, "")),
+            Lookup::In => column.is_in(
+                raw.split(',')
+                    .map(|item| query_to_value(&column, item))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            Lookup::Range => {
+                let (start, end) = raw.split_once(',').unwrap_or((raw, ""));
+                ensure!(
+                    !start.is_empty() && !end.is_empty() && !end.contains(','),
+                    InvalidFieldSnafu {
+                        field: self.query_key(&column),
+                        reason: "expect two comma separated values",
+                    }
+                );
+                column.between(
+                    query_to_value(&column, start)?,
+                    query_to_value(&column, end)?,
+                )
+            }
+            Lookup::IsNull => match raw {
+                "true" | "1" => column.is_null(),
+                "false" | "0" => column.is_not_null(),
+                _ => {
+                    return InvalidFieldSnafu {
+                        field: self.query_key(&column),
+                        reason: "expect `true` or `false`",
+                    }
+                    .fail()
+                }
+            },
+        };
+        Ok(expr)
+    }
+}
+
+impl fmt::Display for Lookup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Lookup {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Lookup::ALL
+            .into_iter()
+            .find(|lookup| lookup.as_str() == s)
+            .ok_or(())
+    }
+}
+
+/// lower case the column, used for case-insensitive matching
+pub(crate) fn lower<C: ColumnTrait>(column: C) -> Expr {
+    Expr::expr(Func::lower(column.into_expr()))
+}
+
+/// like pattern of the query value between the wildcards `prefix` and `suffix`,
+/// `%` and `_` in the value are escaped to match themselves
+pub(crate) fn like(prefix: &str, raw: &str, suffix: &str) -> LikeExpr {
+    let mut pattern = prefix.to_owned();
+    for c in raw.chars() {
+        if matches!(c, '%' | '_' | LIKE_ESCAPE) {
+            pattern.push(LIKE_ESCAPE);
+        }
+        pattern.push(c);
+    }
+    pattern.push_str(suffix);
+    LikeExpr::new(pattern).escape(LIKE_ESCAPE)
+}
+
+/// split query key like `age__gte` into column name and lookup
+/// a key without a known lookup suffix is an `exact` lookup on the whole key
+pub fn split_lookup(key: &str) -> (&str, Lookup) {
+    if let Some((field, lookup)) = key.rsplit_once(LOOKUP_SEPARATOR) {
+        if let Ok(lookup) = Lookup::from_str(lookup) {
+            return (field, lookup);
+        }
+    }
+    (key, Lookup::Exact)
+}
+
+/// build an `AND` condition with all the filters in query
+/// keys in `reserved` are skipped, other keys must be a filterable column with an allowed lookup
+pub fn filter_condition<C, F>(
+    query: &Value,
+    reserved: &[&str],
+    fields: &[C],
+    allowed_lookups: F,
+) -> Result<Condition>
+where
+    C: ColumnTrait,
+    F: Fn(&C) -> Vec<Lookup>,
+{
+    let mut condition = Condition::all();
+    let Some(params) = query.as_object() else {
+        return Ok(condition);
+    };
+    for (key, value) in params {
+        if reserved.contains(&key.as_str()) {
+            continue;
+        }
+        let (field, lookup) = split_lookup(key);
+        let column = fields
+            .iter()
+            .find(|column| column.as_str() == field)
+            .copied();
+        let Some(column) = column else {
+            return InvalidFieldSnafu {
+                field: key,
+                reason: "unknown filter field",
+            }
+            .fail();
+        };
+        ensure!(
+            allowed_lookups(&column).contains(&lookup),
+            InvalidFieldSnafu {
+                field: key,
+                reason: format!("lookup `{lookup}` is not allowed"),
+            }
+        );
+        let raw = match value {
+            Value::String(s) => s.to_owned(),
+            other => other.to_string(),
+        };
+        tracing::debug!("filter {field} with lookup {lookup}: {raw}");
+        condition = condition.add(lookup.condition(column, &raw)?);
+    }
+    Ok(condition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::tests_cfg::cake;
+    use sea_orm::{DbBackend, EntityTrait, Iterable, QueryFilter, QueryTrait};
+
+    fn filter_sql(query: Value) -> Result<String> {
+        let fields = cake::Column::iter().collect::<Vec<_>>();
+        let condition = filter_condition(&query, &["page_size"], &fields, |column| {
+            Lookup::defaults(column).to_vec()
+        })?;
+        Ok(cake::Entity::find()
+            .filter(condition)
+            .build(DbBackend::Postgres)
+            .to_string())
+    }
+
+    #[test]
+    fn query_into_filter_condition() {
+        assert_eq!(split_lookup("id__gte"), ("id", Lookup::Gte));
+        assert_eq!(split_lookup("first__name"), ("first__name", Lookup::Exact));
+        assert_eq!(
+            filter_sql(serde_json::json!({"id__gte": "2", "page_size": "10"})).unwrap(),
+            r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE "cake"."id" >= 2"#
+        );
+        assert_eq!(
+            filter_sql(serde_json::json!({"id__in": "1,2", "name__isnull": "false"})).unwrap(),
+            r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE "cake"."id" IN (1, 2) AND "cake"."name" IS NOT NULL"#
+        );
+        assert_eq!(
+            filter_sql(serde_json::json!({"name__icontains": "Cheese"})).unwrap(),
+            r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE LOWER("cake"."name") LIKE '%cheese%' ESCAPE E'\\'"#
+        );
+        assert_eq!(
+            filter_sql(serde_json::json!({"name__contains": "50%_off"})).unwrap(),
+            r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE "cake"."name" LIKE E'%50\\%\\_off%' ESCAPE E'\\'"#
+        );
+        assert!(filter_sql(serde_json::json!({"id__icontains": "1"})).is_err());
+        assert!(filter_sql(serde_json::json!({"unknown": "1"})).is_err());
+        assert!(filter_sql(serde_json::json!({"id": "one"})).is_err());
+        assert!(filter_sql(serde_json::json!({"id__range": "1"})).is_err());
+    }
+}
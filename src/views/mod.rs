@@ -1,4 +1,35 @@
+pub mod actions;
+pub mod bulk;
+pub mod convert;
+pub mod cursor;
+pub mod etag;
+pub mod expand;
+pub mod fields;
+pub mod filter;
 pub mod macros;
+pub mod nested;
 pub mod operates;
+pub mod ordering;
+pub mod pagination;
+pub mod permission;
+pub mod response;
+pub mod schema;
+pub mod search;
+pub mod soft_delete;
+pub mod validation;
 
-pub use operates::ModelViewExt;
+pub use actions::{ActionRequest, ActionSet, ExtraAction};
+pub use bulk::{BulkItemResult, BulkUpdateItem};
+pub use cursor::CursorPage;
+pub use etag::ETagStyle;
+pub use expand::Expand;
+pub use filter::Lookup;
+pub use nested::ParentEntity;
+pub use operates::{ModelViewExt, PrimaryKeyValue};
+pub use pagination::{Page, PaginationStyle};
+pub use permission::{Action, Permission, RequestParts};
+pub use response::ResponseBody;
+pub use schema::{ModelSchema, ViewSchema};
+pub use search::SearchMode;
+pub use soft_delete::SoftDelete;
+pub use validation::{JsonBody, ValidationErrors};
@@ -0,0 +1,150 @@
+use std::fmt::Debug;
+
+use sea_orm::sea_query::{IntoTableRef, IntoValueTuple};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, PrimaryKeyTrait, RelationDef, Select,
+    Value,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use snafu::OptionExt;
+
+use crate::error::{InternalServerSnafu, Result};
+
+/// primary key value type of the parent entity `P`
+pub type ParentKey<P> = <<P as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType;
+
+/// parent entity of nested routes like `/api/teacher/:teacher_id/students`,
+/// implemented for every entity with a primary key which can be used as a path param
+pub trait ParentEntity: EntityTrait {
+    type Key: Serialize + DeserializeOwned + Clone + Debug + Send + Sync + IntoValueTuple + 'static;
+
+    fn find_by_key(key: Self::Key) -> Select<Self>;
+
+    /// path param name of the parent primary key, like `teacher_id`
+    fn path_param() -> String {
+        format!("{}_id", Self::default().table_name())
+    }
+}
+
+impl<P> ParentEntity for P
+where
+    P: EntityTrait,
+    ParentKey<P>: Serialize + DeserializeOwned + Clone + Sync + 'static,
+{
+    type Key = ParentKey<P>;
+
+    fn find_by_key(key: Self::Key) -> Select<Self> {
+        Self::find_by_id(key)
+    }
+}
+
+/// foreign key values which restrict the instances to the children of a parent,
+/// empty for the top level routes
+#[derive(Debug, Clone)]
+pub struct Scope<C> {
+    columns: Vec<(C, Value)>,
+}
+
+impl<C: ColumnTrait> Scope<C> {
+    pub fn none() -> Self {
+        Self { columns: vec![] }
+    }
+
+    /// the foreign key columns of `relation` from the child to the parent `P` with the parent key values,
+    /// the relation must reference the primary key of the parent
+    /// return 500 if the relation is not from the child table to the table of `P`
+    pub fn parent<P: EntityTrait, K: IntoValueTuple>(
+        relation: RelationDef,
+        key: K,
+    ) -> Result<Self> {
+        let child = C::EntityName::default().into_table_ref();
+        let parent = P::default().into_table_ref();
+        if relation.from_tbl != child || relation.to_tbl != parent {
+            tracing::error!(
+                "relation from {:?} to {:?} is not from the child {child:?} to the parent {parent:?}",
+                relation.from_tbl,
+                relation.to_tbl
+            );
+            return InternalServerSnafu.fail();
+        }
+        let columns = relation
+            .from_col
+            .into_iter()
+            .zip(key.into_value_tuple())
+            .map(|(iden, value)| {
+                let name = iden.to_string();
+                let column = C::from_str(&name).ok();
+                if column.is_none() {
+                    tracing::error!("foreign key `{name}` is not a column of the child entity");
+                }
+                Ok((column.context(InternalServerSnafu)?, value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { columns })
+    }
+
+    pub fn condition(&self) -> Condition {
+        self.columns
+            .iter()
+            .fold(Condition::all(), |condition, (column, value)| {
+                condition.add(column.eq(value.clone()))
+            })
+    }
+
+    /// set the foreign keys of the active model to the parent
+    pub fn apply<A>(&self, active_model: &mut A)
+    where
+        A: ActiveModelTrait,
+        A::Entity: EntityTrait<Column = C>,
+    {
+        for (column, value) in &self.columns {
+            active_model.set(*column, value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests_cfg::{article, tag};
+    use sea_orm::{DbBackend, QueryFilter, QueryTrait, Related, RelationTrait};
+
+    #[test]
+    fn scope_children_to_parent() {
+        let scope = Scope::<article::Column>::parent::<tag::Entity, _>(
+            <article::Entity as Related<tag::Entity>>::to(),
+            "rust".to_owned(),
+        )
+        .unwrap();
+        assert_eq!(
+            article::Entity::find()
+                .filter(scope.condition())
+                .build(DbBackend::Postgres)
+                .to_string(),
+            r#"SELECT "article"."id", "article"."title", "article"."tag_slug" FROM "article" WHERE "article"."tag_slug" = 'rust'"#
+        );
+        let mut active_model = <article::ActiveModel as ActiveModelTrait>::default();
+        scope.apply(&mut active_model);
+        assert_eq!(
+            active_model.tag_slug,
+            sea_orm::ActiveValue::Set("rust".to_owned())
+        );
+        assert_eq!(tag::Entity::path_param(), "tag_id");
+        assert!(Scope::<article::Column>::none().condition().is_empty());
+    }
+
+    #[test]
+    fn reject_relation_not_to_parent() {
+        // the reversed relation from the tag to its articles
+        assert!(Scope::<article::Column>::parent::<tag::Entity, _>(
+            tag::Relation::Article.def(),
+            "rust".to_owned(),
+        )
+        .is_err());
+        assert!(Scope::<article::Column>::parent::<article::Entity, _>(
+            <article::Entity as Related<tag::Entity>>::to(),
+            "rust".to_owned(),
+        )
+        .is_err());
+    }
+}
@@ -1,32 +1,95 @@
 use std::any::type_name;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::SystemTime;
 
 use async_trait::async_trait;
-use axum::extract::Query;
+use axum::extract::{OriginalUri, Query, State};
+use axum::http::{request::Parts, Uri};
 use axum::{
+    body::Body,
     extract::Path,
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{MethodFilter, MethodRouter},
     Json, Router,
 };
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use schemars::gen::SchemaGenerator;
+use sea_orm::sea_query::{Expr, IntoValueTuple};
 use sea_orm::{
-    ActiveModelBehavior, ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
-    Iterable, ModelTrait, PaginatorTrait, PrimaryKeyToColumn, PrimaryKeyTrait, QueryOrder,
-    TryFromU64,
+    ActiveModelBehavior, ActiveModelTrait, ActiveValue, Condition, DatabaseConnection,
+    DatabaseTransaction, EntityTrait, IdenStatic, IntoActiveModel, Iterable, ModelTrait, Order,
+    PaginatorTrait, PrimaryKeyToColumn, PrimaryKeyTrait, QueryFilter, QueryOrder, QuerySelect,
+    QueryTrait, RelationTrait, Select, TransactionTrait,
 };
+use sea_orm::{ConnectionTrait, DbErr, FromQueryResult};
 use serde::Serialize;
-use serde_json::Value;
-use snafu::{OptionExt, ResultExt};
+use serde_json::{Map, Value};
+use snafu::{ensure, OptionExt, ResultExt};
 
-use crate::error::{OperateDatabaseSnafu, PrimaryKeyNotFoundSnafu};
+use crate::error::{
+    ForbiddenSnafu, InternalServerSnafu, InvalidBodySnafu, InvalidFieldSnafu, OperateDatabaseSnafu,
+    PreconditionFailedSnafu, PrimaryKeyNotFoundSnafu,
+};
+use crate::views::actions::{ActionBody, ActionRequest, ActionSet, ActionTarget, ExtraAction};
+use crate::views::bulk::{failed_results, BulkItemResult, BulkUpdateItem};
+use crate::views::convert::json_to_value;
+use crate::views::cursor::{self, fetch_cursor_page, CursorPage, CursorValues};
+use crate::views::etag::{self, hash_etag, is_not_modified, modified_time, ETagStyle};
+use crate::views::expand::{self, Expand, ExpandTree};
+use crate::views::fields;
+use crate::views::filter::{self, Lookup};
+use crate::views::nested::{ParentEntity, Scope};
+use crate::views::ordering;
+use crate::views::pagination::{ListMeta, Page, PageInfo, PaginationStyle};
+use crate::views::permission::{Action, AllowAny, Permission, RequestParts};
+use crate::views::response::ResponseBody;
+use crate::views::schema::{Model, ModelSchema, ViewSchema};
+use crate::views::search::SearchMode;
+use crate::views::soft_delete::SoftDelete;
+use crate::views::validation::{from_json_value, JsonBody};
 use crate::{db, error::Result, generate_by_params};
 
+/// primary key value type of the entity behind active model `T`,
+/// such as `i32`, `i64`, `String` or `Uuid`, used as the path param of `/:id`
+pub type PrimaryKeyValue<T> =
+    <<<T as ActiveModelTrait>::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType;
+
+/// characters escaped in a path segment, all except the unreserved characters of RFC 3986
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// display a key in logs and error messages, parts of a composite key are joined by `,`
+fn key_display<K: Serialize>(key: &K) -> String {
+    match serde_json::json!(key) {
+        Value::String(s) => s,
+        Value::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                Value::String(s) => s.to_owned(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+        other => other.to_string(),
+    }
+}
+
+/// restful http handlers of the entity behind active model `T`,
+/// requests and responses are represented by `S`, default is the sea-orm model, see [`ViewSchema`]
 #[async_trait]
-pub trait ModelViewExt<T>
+pub trait ModelViewExt<T, S = ModelSchema>
 where
     T: ActiveModelTrait + ActiveModelBehavior + Send + 'static + Sync,
+    S: ViewSchema<T> + 'static,
     <T::Entity as EntityTrait>::Model: IntoActiveModel<T> + Serialize + Sync,
     for<'de> <T::Entity as EntityTrait>::Model: serde::de::Deserialize<'de>,
+    PrimaryKeyValue<T>: Serialize + Clone + Sync,
+    for<'de> PrimaryKeyValue<T>: serde::de::Deserialize<'de>,
 {
     #[inline]
     fn modle_name() -> String {
@@ -43,207 +106,2840 @@ where
         db::get_db_connection_pool().await
     }
 
+    /// begin a transaction of the db connection, every write and its hooks run in one transaction
+    async fn begin() -> Result<DatabaseTransaction> {
+        Self::get_db_connection()
+            .await
+            .begin()
+            .await
+            .context(OperateDatabaseSnafu)
+    }
+
+    /// permission policies of every action, a request must be allowed by all of them,
+    /// default allow any request, see [`crate::views::permission`] for the built-in policies
+    fn permissions() -> Vec<Box<dyn Permission<Model<T>>>> {
+        vec![Box::new(AllowAny)]
+    }
+
+    /// check the request before the action, return 401 or 403 to deny it
+    async fn has_permission(action: Action, parts: &Parts) -> Result<()> {
+        Self::permissions()
+            .iter()
+            .try_for_each(|permission| permission.has_permission(action, parts))
+    }
+
+    /// check the instance of the retrieve, update, partial update, delete or a bulk action,
+    /// return 401 or 403 to deny it
+    async fn has_object_permission(action: Action, parts: &Parts, model: &Model<T>) -> Result<()> {
+        Self::permissions()
+            .iter()
+            .try_for_each(|permission| permission.has_object_permission(action, parts, model))
+    }
+
+    /// mark the instances deleted with a column instead of `DELETE`, default `None` to delete the lines,
+    /// soft deleted instances are hidden from every route unless the privileged caller lists or
+    /// retrieves them with `?include_deleted=true`, and can be restored with `POST /api/:id/restore`,
+    /// the column can not be written by the other routes, and expanding this entity as a relation of
+    /// another view needs [`Expand::filter`] with the [`SoftDelete::alive_condition`]
+    /// ```rust,ignore
+    /// fn soft_delete() -> Option<SoftDelete<note::Column>> {
+    ///     Some(SoftDelete::Timestamp(note::Column::DeletedAt))
+    /// }
+    /// ```
+    fn soft_delete() -> Option<SoftDelete<<T::Entity as EntityTrait>::Column>> {
+        None
+    }
+
+    /// check the caller can see and restore the soft deleted instances, default deny all with 403
+    async fn has_privilege(_parts: &Parts) -> Result<()> {
+        ForbiddenSnafu {
+            reason: "only privileged callers can access deleted instances",
+        }
+        .fail()
+    }
+
+    fn include_deleted_param() -> &'static str {
+        "include_deleted"
+    }
+
+    /// whether the query asks for the soft deleted instances too, return 403 if the caller is not privileged
+    async fn include_deleted(query: &Value, parts: &Parts) -> Result<bool> {
+        let include = Self::soft_delete().is_some()
+            && matches!(
+                query
+                    .get(Self::include_deleted_param())
+                    .and_then(Value::as_str),
+                Some("true" | "1")
+            );
+        if include {
+            Self::has_privilege(parts).await?;
+        }
+        Ok(include)
+    }
+
+    /// condition of the instances in the scope, the soft deleted instances are excluded unless `include_deleted`
+    fn scope_condition(
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        include_deleted: bool,
+    ) -> Condition {
+        match Self::soft_delete() {
+            Some(soft_delete) if !include_deleted => {
+                scope.condition().add(soft_delete.alive_condition())
+            }
+            _ => scope.condition(),
+        }
+    }
+
+    /// how the `ETag` of [`ModelViewExt::http_retrieve`] is computed, which is checked with the
+    /// `If-Match` header of update, partial update and delete, default the hash of the output
+    /// ```rust,ignore
+    /// fn etag_style() -> ETagStyle<note::Column> {
+    ///     ETagStyle::Version(note::Column::Version)
+    /// }
+    /// ```
+    fn etag_style() -> ETagStyle<<T::Entity as EntityTrait>::Column> {
+        ETagStyle::Hash
+    }
+
+    fn etag(model: &Model<T>) -> Result<String> {
+        Self::etag_style().etag(model, |model| S::Output::from(model.clone()))
+    }
+
+    /// return 412 if the `If-Match` header of the request not match the instance
+    fn check_if_match(parts: &Parts, model: &Model<T>) -> Result<()> {
+        if !parts.headers.contains_key(header::IF_MATCH) {
+            return Ok(());
+        }
+        etag::check_if_match(&parts.headers, &Self::etag(model)?)
+    }
+
+    /// a timestamp column like `updated_at`, the latest one of the instances is the
+    /// `Last-Modified` of list and retrieve, default `None` without the header
+    fn updated_at_column() -> Option<<T::Entity as EntityTrait>::Column> {
+        None
+    }
+
+    /// `Cache-Control` of list and retrieve like `HeaderValue::from_static("private, max-age=5")`,
+    /// default `None` without the header
+    fn cache_control() -> Option<HeaderValue> {
+        None
+    }
+
+    /// the largest body of list or retrieve hashed into its `ETag`, a larger one answers 500,
+    /// default 16 MiB
+    fn max_etag_body_size() -> usize {
+        16 * 1024 * 1024
+    }
+
+    /// the latest [`ModelViewExt::updated_at_column`] of the models
+    fn last_modified<'a>(models: impl IntoIterator<Item = &'a Model<T>>) -> Option<SystemTime>
+    where
+        Model<T>: 'a,
+    {
+        let column = Self::updated_at_column()?;
+        models
+            .into_iter()
+            .filter_map(|model| modified_time(&model.get(column)))
+            .max()
+    }
+
+    /// add `ETag`, `Last-Modified` and `Cache-Control` to a successful response of list or retrieve,
+    /// the `ETag` is the hash of the body if the response has none,
+    /// return 304 without the body if the request is not modified
+    async fn conditional_response(
+        parts: &Parts,
+        response: Response,
+        last_modified: Option<SystemTime>,
+    ) -> Result<Response> {
+        let (head, body) = response.into_parts();
+        if head.status != StatusCode::OK {
+            return Ok(Response::from_parts(head, body));
+        }
+        let (etag, body) = match head.headers.get(header::ETAG) {
+            Some(etag) => (etag.clone(), body),
+            None => {
+                let bytes = axum::body::to_bytes(body, Self::max_etag_body_size())
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("read the body to compute etag failed: {e}");
+                        InternalServerSnafu.build()
+                    })?;
+                let etag = HeaderValue::from_str(&hash_etag(&bytes))
+                    .ok()
+                    .context(InternalServerSnafu)?;
+                (etag, Body::from(bytes))
+            }
+        };
+        let mut headers = vec![(header::ETAG, etag.clone())];
+        if let Some(last_modified) = last_modified {
+            let value = HeaderValue::from_str(&httpdate::fmt_http_date(last_modified))
+                .ok()
+                .context(InternalServerSnafu)?;
+            headers.push((header::LAST_MODIFIED, value));
+        }
+        if let Some(cache_control) = Self::cache_control() {
+            headers.push((header::CACHE_CONTROL, cache_control));
+        }
+        let not_modified = is_not_modified(
+            &parts.headers,
+            etag.to_str().unwrap_or_default(),
+            last_modified,
+        );
+        let mut response = if not_modified {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            Response::from_parts(head, body)
+        };
+        response.headers_mut().extend(headers);
+        Ok(response)
+    }
+
+    /// update the instance read as `model` with the active model, return 412 if the version
+    /// column of [`ModelViewExt::etag_style`] is changed by another request meanwhile
+    async fn save_update<C>(mut active_model: T, model: &Model<T>, db: &C) -> Result<Model<T>>
+    where
+        C: ConnectionTrait + Sync,
+    {
+        let ETagStyle::Version(column) = Self::etag_style() else {
+            return active_model.update(db).await.context(OperateDatabaseSnafu);
+        };
+        let version = model.get(column);
+        active_model.set(column, etag::next_version(&column, &version)?);
+        <T::Entity as EntityTrait>::update(active_model)
+            .filter(etag::version_condition(&column, version))
+            .exec(db)
+            .await
+            .or_else(|e| match e {
+                DbErr::RecordNotUpdated => PreconditionFailedSnafu {
+                    reason: "the instance was changed by another request",
+                }
+                .fail(),
+                e => Err(e).context(OperateDatabaseSnafu),
+            })
+    }
+
+    /// delete the instance read as `model`, return 412 if the version column
+    /// of [`ModelViewExt::etag_style`] is changed by another request meanwhile
+    async fn save_delete<C>(model: &Model<T>, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait + Sync,
+    {
+        let ETagStyle::Version(column) = Self::etag_style() else {
+            model
+                .clone()
+                .delete(db)
+                .await
+                .context(OperateDatabaseSnafu)?;
+            return Ok(());
+        };
+        let active_model: T = model.clone().into_active_model();
+        let result = <T::Entity as EntityTrait>::delete(active_model)
+            .filter(etag::version_condition(&column, model.get(column)))
+            .exec(db)
+            .await
+            .context(OperateDatabaseSnafu)?;
+        ensure!(
+            result.rows_affected > 0,
+            PreconditionFailedSnafu {
+                reason: "the instance was changed by another request",
+            }
+        );
+        Ok(())
+    }
+
+    /// body of [`ModelViewExt::http_create`], default return the created instance
+    fn create_response() -> ResponseBody {
+        ResponseBody::Instance
+    }
+
+    /// body of [`ModelViewExt::http_update`], default return an empty body
+    fn update_response() -> ResponseBody {
+        ResponseBody::Empty
+    }
+
     /// POST a json body to /api and create a line in database
-    /// return http 201 StatusCode::CREATED
+    /// return http 201 StatusCode::CREATED with a `Location` header of the created instance,
+    /// the body depends on [`ModelViewExt::create_response`]
     async fn http_create(
-        Json(data): Json<<T::Entity as EntityTrait>::Model>,
-    ) -> Result<StatusCode> {
+        RequestParts(parts): RequestParts,
+        OriginalUri(uri): OriginalUri,
+        JsonBody(data): JsonBody<S::Create>,
+    ) -> Result<Response> {
+        Self::has_permission(Action::Create, &parts).await?;
+        Self::create(&uri, data, &Scope::none(), &parts).await
+    }
+
+    /// create an instance in the scope, the foreign keys of the scope are always set
+    async fn create(
+        uri: &Uri,
+        data: S::Create,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        parts: &Parts,
+    ) -> Result<Response> {
         let mut active_model: T = data.into_active_model();
+        Self::check_soft_delete_column(&active_model)?;
         tracing::debug!(
             "[{}] http create: before not set pk {active_model:?}",
             Self::modle_name()
         );
-        for key in <T::Entity as EntityTrait>::PrimaryKey::iter() {
-            let col = key.into_column();
-            active_model.not_set(col);
-        }
+        Self::unset_auto_increment_primary_key(&mut active_model);
+        let txn = Self::begin().await?;
+        Self::before_create(&mut active_model, parts, &txn).await?;
+        scope.apply(&mut active_model);
+        Self::validate(&active_model, Action::Create).await?;
         tracing::debug!(
             "[{}] http create: active model is {active_model:?}",
             Self::modle_name()
         );
         let result = active_model
-            .insert(Self::get_db_connection().await)
+            .insert(&txn)
             .await
             .context(OperateDatabaseSnafu)?;
+        Self::after_create(&result, parts, &txn).await?;
+        txn.commit().await.context(OperateDatabaseSnafu)?;
         tracing::debug!(
             "[{}] http create: create model {result:?}",
             Self::modle_name()
         );
-        Ok(StatusCode::CREATED)
+        let location = format!(
+            "{}/{}",
+            uri.path().trim_end_matches('/'),
+            Self::model_primary_key_path(&result)
+        );
+        let mut response = match Self::create_response() {
+            ResponseBody::Empty => StatusCode::CREATED.into_response(),
+            ResponseBody::Instance => {
+                (StatusCode::CREATED, Json(S::Output::from(result))).into_response()
+            }
+        };
+        if let Ok(value) = HeaderValue::from_str(&location) {
+            response.headers_mut().insert(header::LOCATION, value);
+        }
+        Ok(response)
+    }
+
+    /// leave the primary keys to database if they are auto increment
+    fn unset_auto_increment_primary_key(active_model: &mut T) {
+        if <T::Entity as EntityTrait>::PrimaryKey::auto_increment() {
+            for key in <T::Entity as EntityTrait>::PrimaryKey::iter() {
+                let col = key.into_column();
+                active_model.not_set(col);
+            }
+        }
+    }
+
+    /// primary key values of the model as the percent-encoded detail path segments, like `1` or `1/2`
+    fn model_primary_key_path(model: &<T::Entity as EntityTrait>::Model) -> String {
+        let json = serde_json::json!(model);
+        <T::Entity as EntityTrait>::PrimaryKey::iter()
+            .map(|key| match &json[key.into_column().as_str()] {
+                Value::String(s) => utf8_percent_encode(s, PATH_SEGMENT).to_string(),
+                other => utf8_percent_encode(&other.to_string(), PATH_SEGMENT).to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// path param names of the detail route, `id` for a single primary key,
+    /// or every primary key column name for a composite primary key
+    fn primary_key_path_params() -> Vec<String> {
+        let columns = <T::Entity as EntityTrait>::PrimaryKey::iter()
+            .map(|key| key.into_column().as_str().to_owned())
+            .collect::<Vec<_>>();
+        if columns.len() > 1 {
+            columns
+        } else {
+            vec!["id".to_owned()]
+        }
+    }
+
+    /// detail route like `/:id`, or `/:student_id/:course_id` for a composite primary key
+    fn detail_path() -> String {
+        Self::primary_key_path_params()
+            .iter()
+            .map(|param| format!("/:{param}"))
+            .collect()
     }
 
-    fn set_model_primary_key(active_model: &mut T, value: u64) {
-        if let Some(key) = <T::Entity as EntityTrait>::PrimaryKey::iter().next() {
-            let col = key.into_column();
-            active_model.set(col, sea_orm::Value::BigInt(Some(value as i64)));
+    fn set_model_primary_key(active_model: &mut T, value: PrimaryKeyValue<T>) {
+        let keys = <T::Entity as EntityTrait>::PrimaryKey::iter();
+        for (key, value) in keys.zip(value.into_value_tuple()) {
+            active_model.set(key.into_column(), value);
         }
     }
 
+    /// display primary key in logs and error messages, parts of a composite key are joined by `,`
+    fn primary_key_display(pk: &PrimaryKeyValue<T>) -> String {
+        key_display(pk)
+    }
+
     /// PUT a json body to /api/:id
     /// change a line in database
-    /// return http 200 StatusCode::OK
+    /// return http 200 StatusCode::OK, the body depends on [`ModelViewExt::update_response`]
     async fn http_update(
-        Path(pk): Path<u64>,
-        Json(data): Json<<T::Entity as EntityTrait>::Model>,
-    ) -> Result<StatusCode> {
-        tracing::debug!("[{}] http update check: {pk}", Self::modle_name());
-        Self::check_instance_exists(pk).await?;
+        RequestParts(parts): RequestParts,
+        Path(pk): Path<PrimaryKeyValue<T>>,
+        JsonBody(data): JsonBody<S::Update>,
+    ) -> Result<Response> {
+        Self::has_permission(Action::Update, &parts).await?;
+        Self::update(pk, data, &Scope::none(), &parts).await
+    }
+
+    /// update an instance in the scope, return 404 if the instance is not in the scope
+    async fn update(
+        pk: PrimaryKeyValue<T>,
+        data: S::Update,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        parts: &Parts,
+    ) -> Result<Response> {
+        tracing::debug!("[{}] http update check: {pk:?}", Self::modle_name());
+        let txn = Self::begin().await?;
+        let model = Self::find_scoped_instance(&txn, pk.clone(), scope, false).await?;
+        Self::has_object_permission(Action::Update, parts, &model).await?;
+        Self::check_if_match(parts, &model)?;
         let mut active_model = data.into_active_model().reset_all();
-        Self::set_model_primary_key(&mut active_model, pk);
+        Self::check_soft_delete_column(&active_model)?;
+        Self::set_model_primary_key(&mut active_model, pk.clone());
+        Self::before_update(&mut active_model, parts, &txn).await?;
+        scope.apply(&mut active_model);
+        Self::validate(&active_model, Action::Update).await?;
         tracing::debug!(
-            "[{}] http update: active pk: {pk} active model: {active_model:?}",
+            "[{}] http update: active pk: {pk:?} active model: {active_model:?}",
             Self::modle_name()
         );
-        let result = active_model
-            .update(Self::get_db_connection().await)
-            .await
-            .context(OperateDatabaseSnafu)?;
+        let result = Self::save_update(active_model, &model, &txn).await?;
+        Self::after_update(&result, parts, &txn).await?;
+        txn.commit().await.context(OperateDatabaseSnafu)?;
         tracing::debug!("[{}] http update: result {result:?}", Self::modle_name());
-        Ok(StatusCode::OK)
+        Ok(match Self::update_response() {
+            ResponseBody::Empty => StatusCode::OK.into_response(),
+            ResponseBody::Instance => Json(S::Output::from(result)).into_response(),
+        })
     }
 
-    async fn check_instance_exists(pk: u64) -> Result<<T::Entity as EntityTrait>::Model> {
-        Ok(
-            <T::Entity as EntityTrait>::find_by_id(Self::exchange_primary_key(pk))
-                .one(Self::get_db_connection().await)
-                .await
-                .context(OperateDatabaseSnafu)?
-                .context(PrimaryKeyNotFoundSnafu { pk })?,
+    async fn check_instance_exists(
+        pk: PrimaryKeyValue<T>,
+    ) -> Result<<T::Entity as EntityTrait>::Model> {
+        Self::check_scoped_instance_exists(pk, &Scope::none()).await
+    }
+
+    /// find the instance in the scope, return 404 if not found
+    async fn check_scoped_instance_exists(
+        pk: PrimaryKeyValue<T>,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+    ) -> Result<<T::Entity as EntityTrait>::Model> {
+        Self::find_scoped_instance(Self::get_db_connection().await, pk, scope, false).await
+    }
+
+    /// find the instance in the scope with the connection or transaction, return 404 if not found
+    async fn find_scoped_instance<C>(
+        db: &C,
+        pk: PrimaryKeyValue<T>,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        include_deleted: bool,
+    ) -> Result<<T::Entity as EntityTrait>::Model>
+    where
+        C: ConnectionTrait + Sync,
+    {
+        let display = Self::primary_key_display(&pk);
+        <T::Entity as EntityTrait>::find_by_id(pk)
+            .filter(Self::scope_condition(scope, include_deleted))
+            .one(db)
+            .await
+            .context(OperateDatabaseSnafu)?
+            .context(PrimaryKeyNotFoundSnafu { pk: display })
+    }
+
+    /// PATCH a json object body to /api/:id
+    /// only the fields in body will be changed, every key must be a column set by [`ViewSchema::Update`]
+    /// return http 200 with the updated instance, 404 if not matched a query,
+    /// or 400 if a key is unknown, not writable, a primary key or the value is invalid
+    async fn http_partial_update(
+        RequestParts(parts): RequestParts,
+        Path(pk): Path<PrimaryKeyValue<T>>,
+        JsonBody(data): JsonBody<Value>,
+    ) -> Result<Json<S::Output>> {
+        Self::has_permission(Action::PartialUpdate, &parts).await?;
+        Self::partial_update(pk, data, &Scope::none(), &parts).await
+    }
+
+    /// partial update an instance in the scope, the foreign keys of the scope can not be changed
+    async fn partial_update(
+        pk: PrimaryKeyValue<T>,
+        data: Value,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        parts: &Parts,
+    ) -> Result<Json<S::Output>> {
+        tracing::debug!("[{}] http patch check: {pk:?}", Self::modle_name());
+        let fields = data.as_object().context(InvalidBodySnafu {
+            reason: "expect a json object",
+        })?;
+        let txn = Self::begin().await?;
+        let model = Self::find_scoped_instance(&txn, pk.clone(), scope, false).await?;
+        Self::has_object_permission(Action::PartialUpdate, parts, &model).await?;
+        Self::check_if_match(parts, &model)?;
+        let mut active_model: T = model.clone().into_active_model();
+        Self::set_fields(&mut active_model, &model, fields)?;
+        Self::before_update(&mut active_model, parts, &txn).await?;
+        scope.apply(&mut active_model);
+        Self::validate(&active_model, Action::PartialUpdate).await?;
+        tracing::debug!(
+            "[{}] http patch: pk: {pk:?} active model: {active_model:?}",
+            Self::modle_name()
+        );
+        let result = Self::save_update(active_model, &model, &txn).await?;
+        Self::after_update(&result, parts, &txn).await?;
+        txn.commit().await.context(OperateDatabaseSnafu)?;
+        tracing::debug!("[{}] http patch: result {result:?}", Self::modle_name());
+        Ok(Json(S::Output::from(result)))
+    }
+
+    /// validate the active model before it is created or updated by the action,
+    /// add the messages of every invalid field into [`crate::views::ValidationErrors`] and return
+    /// its `into_result()` to reject the request with 400, default accept all
+    /// ```rust,ignore
+    /// async fn validate(active_model: &student::ActiveModel, _action: Action) -> Result<()> {
+    ///     let mut errors = ValidationErrors::new();
+    ///     if let ActiveValue::Set(age) | ActiveValue::Unchanged(age) = active_model.age {
+    ///         errors.check(age >= 0, "age", "must be >= 0");
+    ///     }
+    ///     errors.into_result()
+    /// }
+    /// ```
+    async fn validate(_active_model: &T, _action: Action) -> Result<()> {
+        Ok(())
+    }
+
+    /// called before the active model is inserted, in the transaction of the insert,
+    /// change the active model here like setting an owner, return an error to abort the create
+    /// ```rust,ignore
+    /// async fn before_create(
+    ///     active_model: &mut article::ActiveModel,
+    ///     parts: &Parts,
+    ///     _txn: &DatabaseTransaction,
+    /// ) -> Result<()> {
+    ///     let identity = parts.extensions.get::<Identity>().context(UnauthorizedSnafu {
+    ///         reason: "authentication credentials were not provided",
+    ///     })?;
+    ///     active_model.owner = ActiveValue::Set(identity.subject.clone());
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn before_create(
+        _active_model: &mut T,
+        _parts: &Parts,
+        _txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// called after the instance is inserted, in the transaction of the insert,
+    /// return an error to roll back the create
+    async fn after_create(
+        _model: &Model<T>,
+        _parts: &Parts,
+        _txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// called before the active model is updated by update, partial update or bulk update,
+    /// in the transaction of the update, return an error to abort the update
+    async fn before_update(
+        _active_model: &mut T,
+        _parts: &Parts,
+        _txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// called after the instance is updated, in the transaction of the update,
+    /// return an error to roll back the update
+    async fn after_update(
+        _model: &Model<T>,
+        _parts: &Parts,
+        _txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// called before the instance is deleted by delete or bulk delete,
+    /// in the transaction of the delete, return an error to abort the delete
+    async fn before_delete(
+        _model: &Model<T>,
+        _parts: &Parts,
+        _txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// called after the instance is deleted, in the transaction of the delete,
+    /// return an error to roll back the delete
+    async fn after_delete(
+        _model: &Model<T>,
+        _parts: &Parts,
+        _txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// set the json fields into the active model through [`ViewSchema::Update`],
+    /// the fields are laid over the json of the current model and deserialized as the update
+    /// schema, so only the columns the update schema sets are writable
+    /// return 400 if a key is unknown, not writable, a primary key or the value is invalid
+    fn set_fields(
+        active_model: &mut T,
+        model: &Model<T>,
+        fields: &Map<String, Value>,
+    ) -> Result<()> {
+        let mut columns = vec![];
+        for key in fields.keys() {
+            let column = <T::Entity as EntityTrait>::Column::from_str(key)
+                .ok()
+                .context(InvalidFieldSnafu {
+                    field: key,
+                    reason: "unknown field",
+                })?;
+            ensure!(
+                !<T::Entity as EntityTrait>::PrimaryKey::iter()
+                    .any(|primary_key| primary_key.into_column().as_str() == column.as_str()),
+                InvalidFieldSnafu {
+                    field: key,
+                    reason: "primary key can not be changed",
+                }
+            );
+            columns.push(column);
+        }
+        let mut data = match serde_json::to_value(model) {
+            Ok(Value::Object(data)) => data,
+            _ => return InternalServerSnafu.fail(),
+        };
+        data.extend(fields.clone());
+        let update: S::Update = from_json_value(Value::Object(data))?;
+        let updated: T = update.into_active_model();
+        for (key, column) in fields.keys().zip(columns) {
+            tracing::debug!("[{}] set field {key}", Self::modle_name());
+            let value = updated
+                .get(column)
+                .into_value()
+                .context(InvalidFieldSnafu {
+                    field: key,
+                    reason: "field is not writable",
+                })?;
+            active_model.set(column, value);
+        }
+        Self::check_soft_delete_column(active_model)
+    }
+
+    /// return 400 if the [`ModelViewExt::soft_delete`] column is set to mark the instance deleted,
+    /// the column is only changed by delete and restore
+    fn check_soft_delete_column(active_model: &T) -> Result<()> {
+        let Some(soft_delete) = Self::soft_delete() else {
+            return Ok(());
+        };
+        let column = soft_delete.column();
+        if let ActiveValue::Set(value) | ActiveValue::Unchanged(value) = active_model.get(column) {
+            ensure!(
+                value == soft_delete.restored_value()?,
+                InvalidFieldSnafu {
+                    field: column.as_str(),
+                    reason: "field can not be changed",
+                }
+            );
+        }
+        Ok(())
+    }
+
+    /// column for the default descending ordering, default is the first primary key
+    fn order_by_desc() -> <T::Entity as EntityTrait>::Column {
+        <T::Entity as EntityTrait>::PrimaryKey::iter()
+            .next()
+            .expect("entity has no primary key")
+            .into_column()
+    }
+
+    /// ordering used when query has no ordering param
+    /// default is descending by [`ModelViewExt::order_by_desc`], override this for a multi column ordering
+    fn default_ordering() -> Vec<(<T::Entity as EntityTrait>::Column, Order)> {
+        vec![(Self::order_by_desc(), Order::Desc)]
+    }
+
+    /// columns which can be ordered by query string like /api?ordering=-create_time,name
+    /// default is all the columns
+    fn ordering_fields() -> Vec<<T::Entity as EntityTrait>::Column> {
+        <T::Entity as EntityTrait>::Column::iter().collect()
+    }
+
+    #[inline]
+    fn ordering_param() -> &'static str {
+        "ordering"
+    }
+
+    /// parse the ordering from query string, or use [`ModelViewExt::default_ordering`]
+    /// return 400 if a field is not in [`ModelViewExt::ordering_fields`]
+    fn ordering(query: &Value) -> Result<Vec<(<T::Entity as EntityTrait>::Column, Order)>> {
+        match query.get(Self::ordering_param()).and_then(Value::as_str) {
+            Some(raw) if !raw.trim().is_empty() => {
+                ordering::parse_ordering(raw, &Self::ordering_fields())
+            }
+            _ => Ok(Self::default_ordering()),
+        }
+    }
+
+    /// columns which can be filtered with query string like /api?age=18 or /api?age__gte=18
+    /// default is empty which disable filtering, a secret column like a password hash
+    /// should never be listed since the lookups reveal its value
+    fn filter_fields() -> Vec<<T::Entity as EntityTrait>::Column> {
+        vec![]
+    }
+
+    /// lookups allowed for a filterable column, default is [`Lookup::defaults`] by the column type
+    fn filter_lookups(column: &<T::Entity as EntityTrait>::Column) -> Vec<Lookup> {
+        Lookup::defaults(column).to_vec()
+    }
+
+    /// text columns matched by query string like /api?search=foo bar
+    /// default is empty which disable searching
+    fn search_fields() -> Vec<<T::Entity as EntityTrait>::Column> {
+        vec![]
+    }
+
+    /// default search with case-insensitive like,
+    /// change into [`SearchMode::FullText`] for postgres full text search
+    fn search_mode() -> SearchMode {
+        SearchMode::default()
+    }
+
+    #[inline]
+    fn search_param() -> &'static str {
+        "search"
+    }
+
+    /// build the search condition from query string across [`ModelViewExt::search_fields`]
+    fn search_condition(query: &Value) -> Option<Condition> {
+        let search = query.get(Self::search_param())?.as_str()?;
+        Self::search_mode().condition(&Self::search_fields(), search)
+    }
+
+    /// columns which can be selected by query string like /api?fields=id,name or /api?exclude=region
+    /// default is all the columns, only the ones in [`ViewSchema::Output`] can be selected
+    fn sparse_fields() -> Vec<<T::Entity as EntityTrait>::Column> {
+        <T::Entity as EntityTrait>::Column::iter().collect()
+    }
+
+    /// columns which are always selected with `?fields=` or `?exclude=`, default is the primary keys
+    fn always_included_fields() -> Vec<<T::Entity as EntityTrait>::Column> {
+        <T::Entity as EntityTrait>::PrimaryKey::iter()
+            .map(|key| key.into_column())
+            .collect()
+    }
+
+    #[inline]
+    fn fields_param() -> &'static str {
+        "fields"
+    }
+
+    #[inline]
+    fn exclude_param() -> &'static str {
+        "exclude"
+    }
+
+    /// columns of `columns` which are top level properties of the json schema of [`ViewSchema::Output`]
+    fn output_columns(
+        columns: Vec<<T::Entity as EntityTrait>::Column>,
+    ) -> Vec<<T::Entity as EntityTrait>::Column> {
+        let output = SchemaGenerator::default().into_root_schema_for::<S::Output>();
+        let properties = output
+            .schema
+            .object
+            .map(|object| object.properties)
+            .unwrap_or_default();
+        columns
+            .into_iter()
+            .filter(|column| properties.contains_key(column.as_str()))
+            .collect()
+    }
+
+    /// columns which can be named by `?fields=` and `?exclude=`,
+    /// the ones of [`ModelViewExt::sparse_fields`] in [`ViewSchema::Output`]
+    fn selectable_fields() -> Vec<<T::Entity as EntityTrait>::Column> {
+        Self::output_columns(Self::sparse_fields())
+    }
+
+    /// columns to select from query string, `None` means all the columns,
+    /// checked before the query so the result never depends on the rows
+    /// return 400 if a field is unknown or not in [`ModelViewExt::selectable_fields`]
+    fn field_projection(query: &Value) -> Result<Option<Vec<<T::Entity as EntityTrait>::Column>>> {
+        fields::projection(
+            query.get(Self::fields_param()).and_then(Value::as_str),
+            query.get(Self::exclude_param()).and_then(Value::as_str),
+            &Self::selectable_fields(),
+            &Self::output_columns(Self::always_included_fields()),
         )
     }
 
-    /// TODO: patch need value trans into active model value
-    /// PATCH a json body to /api/:id
-    /// return http 200 StatusCode::OK if matched, or return 404 if not matched a query
-    // async fn http_partial_update(
-    //     Path(pk): Path<u64>,
-    //     Json(data): Json<Value>,
-    // ) -> Result<StatusCode> {
-    //     tracing::debug!("[{}] http patch check: {pk}", Self::modle_name());
-    //     let model = Self::check_instance_exists(pk).await?;
-    //     let mut active_model = model.into_active_model();
-    //     tracing::debug!(
-    //         "[{}] http patch: original pk: {pk} active model: {active_model:?}",
-    //         Self::modle_name()
-    //     );
-    //     for (k, v) in data.as_object().unwrap() {
-    //         tracing::debug!("[{}] http patch set {}: {}", Self::modle_name(), k, v);
-    //         if let Ok(column) = <T::Entity as EntityTrait>::Column::from_str(k) {
-    //             // let col_type = column.def().get_column_type()
-    //             // active_model.set(column, v);
-    //         }
-    //     }
-    //     tracing::debug!(
-    //         "[{}] http patch: ative model pk: {pk} active model: {active_model:?}",
-    //         Self::modle_name()
-    //     );
-    //     let result = active_model.update(Self::get_db_connection().await).await;
-    //     tracing::debug!("[{}] http patch: result {result:?}", Self::modle_name());
-    //     Ok(StatusCode::OK)
-    // }
-
-    fn order_by_desc() -> <T::Entity as EntityTrait>::Column;
+    /// query params which are not treated as filters
+    fn reserved_query_params() -> Vec<&'static str> {
+        vec![
+            Self::page_size_param(),
+            Self::page_num_param(),
+            Self::ordering_param(),
+            Self::search_param(),
+            Self::cursor_param(),
+            Self::fields_param(),
+            Self::exclude_param(),
+            Self::expand_param(),
+            Self::include_deleted_param(),
+        ]
+    }
+
+    /// build the filter condition from query string
+    /// return 400 if a query key is not a filterable column or the lookup is not allowed
+    fn filter_condition(query: &Value) -> Result<Condition> {
+        filter::filter_condition(
+            query,
+            &Self::reserved_query_params(),
+            &Self::filter_fields(),
+            Self::filter_lookups,
+        )
+    }
 
     /// GET list results with /api
     /// you can set page_size and page_num to page results with url like /api?page_size=10 or /api?page_size=10&page_num=1
-    /// return results with StatusCode::OK
-    async fn http_list(Query(query): Query<Value>) -> Result<Json<Value>> {
+    /// filter results with url like /api?age__gte=18&name__icontains=bob, see [`Lookup`] for all the lookups
+    /// order results with url like /api?ordering=-create_time,name
+    /// search results in [`ModelViewExt::search_fields`] with url like /api?search=foo bar
+    /// select only some columns with url like /api?fields=id,name or /api?exclude=region
+    /// embed related models in [`ModelViewExt::expand_relations`] with url like /api?expand=teacher,courses.teacher
+    /// return results with StatusCode::OK, the body and headers depend on [`ModelViewExt::pagination_style`],
+    /// or 304 if `If-None-Match` or `If-Modified-Since` shows the results are not modified
+    async fn http_list(
+        RequestParts(parts): RequestParts,
+        OriginalUri(uri): OriginalUri,
+        Query(query): Query<Value>,
+    ) -> Result<Response> {
+        Self::has_permission(Action::List, &parts).await?;
+        Self::list(&uri, &query, &Scope::none(), &parts).await
+    }
+
+    /// list the instances in the scope
+    async fn list(
+        uri: &Uri,
+        query: &Value,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        parts: &Parts,
+    ) -> Result<Response> {
+        let include_deleted = Self::include_deleted(query, parts).await?;
+        let select = Self::filtered_select(query, scope, include_deleted)?;
+        let projection = Self::field_projection(query)?;
+        let expand = Self::expand_trees(query)?;
+        // the related models are not covered by the `Last-Modified` of the instances
+        let (response, last_modified) = match (projection, expand.is_empty()) {
+            (None, true) => {
+                let (models, meta) =
+                    Self::fetch_list::<Model<T>>(select, query, cursor::model_values).await?;
+                let last_modified = Self::last_modified(&models);
+                let results = models.into_iter().map(S::Output::from).collect::<Vec<_>>();
+                (Self::list_response(results, meta, uri), last_modified)
+            }
+            (None, false) => {
+                let (models, meta) =
+                    Self::fetch_list::<Model<T>>(select, query, cursor::model_values).await?;
+                let results = Self::expand_outputs(models, &expand).await?;
+                (Self::list_response(results, meta, uri), None)
+            }
+            (Some(columns), true) => {
+                // the cursor is built from the ordering columns and `Last-Modified` from the
+                // updated at column, so they are selected as well
+                let mut selected = columns.clone();
+                let mut extra = Self::updated_at_column().into_iter().collect::<Vec<_>>();
+                if Self::pagination_style() == PaginationStyle::Cursor {
+                    extra.extend(
+                        Self::cursor_ordering()
+                            .into_iter()
+                            .map(|(column, _)| column),
+                    );
+                }
+                for column in extra {
+                    if !selected.iter().any(|c| c.as_str() == column.as_str()) {
+                        selected.push(column);
+                    }
+                }
+                let select = select.select_only().columns(selected.clone());
+                let (mut rows, meta) =
+                    Self::fetch_list::<Value>(select, query, cursor::row_values).await?;
+                let last_modified = Self::updated_at_column().and_then(|column| {
+                    rows.iter()
+                        .filter_map(|row| json_to_value(&column, row.get(column.as_str())?).ok())
+                        .filter_map(|value| modified_time(&value))
+                        .max()
+                });
+                if selected.len() > columns.len() {
+                    for row in &mut rows {
+                        if let Value::Object(map) = row {
+                            map.retain(|key, _| columns.iter().any(|c| c.as_str() == key));
+                        }
+                    }
+                }
+                (Self::list_response(rows, meta, uri), last_modified)
+            }
+            (Some(_), false) => return Self::expand_with_fields_error(),
+        };
+        Self::conditional_response(parts, response, last_modified).await
+    }
+
+    /// select the instances in the scope matched the filter and search query
+    fn filtered_select(
+        query: &Value,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        include_deleted: bool,
+    ) -> Result<Select<T::Entity>> {
+        let mut select = T::Entity::find()
+            .filter(Self::scope_condition(scope, include_deleted))
+            .filter(Self::filter_condition(query)?);
+        if let Some(condition) = Self::search_condition(query) {
+            select = select.filter(condition);
+        }
+        Ok(select)
+    }
+
+    /// fetch the results of `select` as `M` with the [`ModelViewExt::pagination_style`],
+    /// `cursor_values` reads the ordering columns of a result for the cursors,
+    /// `?page_size=` is lowered to [`ModelViewExt::max_page_size`]
+    async fn fetch_list<M>(
+        mut select: Select<T::Entity>,
+        query: &Value,
+        cursor_values: CursorValues<M, <T::Entity as EntityTrait>::Column>,
+    ) -> Result<(Vec<M>, ListMeta)>
+    where
+        M: FromQueryResult + Serialize + Send + Sync,
+    {
         let db = Self::get_db_connection().await;
-        let page_size = Self::get_page_size(&query);
-        let results = if !page_size.eq(&0) {
-            T::Entity::find()
-                .order_by_desc(Self::order_by_desc())
-                .into_model()
-                .paginate(db, page_size)
-                .fetch_page(Self::get_page_num(&query))
+        let page_size = Self::get_page_size(query).min(Self::max_page_size());
+        let style = Self::pagination_style();
+        if style == PaginationStyle::Cursor {
+            ensure!(
+                query
+                    .get(Self::ordering_param())
+                    .and_then(Value::as_str)
+                    .is_none_or(|raw| raw.trim().is_empty()),
+                InvalidFieldSnafu {
+                    field: Self::ordering_param(),
+                    reason: "can not be used with cursor pagination",
+                }
+            );
+            let ordering = Self::cursor_ordering();
+            let cursor = query.get(Self::cursor_param()).and_then(Value::as_str);
+            let page_size = if page_size.eq(&0) {
+                Self::default_page_size()
+            } else {
+                page_size
+            };
+            let page = fetch_cursor_page::<_, _, _, M>(
+                select,
+                db,
+                &ordering,
+                cursor,
+                page_size,
+                cursor_values,
+            )
+            .await?;
+            tracing::debug!("http list: fetch results len {}", page.results.len());
+            let meta = ListMeta::Cursor {
+                next_cursor: page.next_cursor,
+                prev_cursor: page.prev_cursor,
+            };
+            return Ok((page.results, meta));
+        }
+        for (column, order) in Self::ordering(query)? {
+            select = select.order_by(column, order);
+        }
+        let (results, page_info) = if !page_size.eq(&0) {
+            let index = Self::get_page_num(query);
+            let paginator = select.into_model::<M>().paginate(db, page_size);
+            let page_info = if style == PaginationStyle::Plain {
+                None
+            } else {
+                let count = paginator
+                    .num_items_and_pages()
+                    .await
+                    .context(OperateDatabaseSnafu)?;
+                Some(PageInfo {
+                    count: count.number_of_items,
+                    index,
+                    pages: count.number_of_pages,
+                })
+            };
+            let results = paginator
+                .fetch_page(index)
                 .await
-                .context(OperateDatabaseSnafu)?
+                .context(OperateDatabaseSnafu)?;
+            (results, page_info)
         } else {
             tracing::debug!("http list: fetch all");
-            <T::Entity as EntityTrait>::find()
-                .order_by_desc(Self::order_by_desc())
-                .all(Self::get_db_connection().await)
+            let results = select
+                .into_model::<M>()
+                .all(db)
                 .await
-                .context(OperateDatabaseSnafu)?
+                .context(OperateDatabaseSnafu)?;
+            let page_info = PageInfo {
+                count: results.len() as u64,
+                index: 0,
+                pages: u64::from(!results.is_empty()),
+            };
+            (results, Some(page_info))
         };
         tracing::debug!("http list: fetch results len {}", results.len());
-        Ok(Json(serde_json::json!(results)))
+        Ok((results, ListMeta::Offset(page_info)))
     }
 
-    /// GET a single query result with /api/:id
-    /// return http 200 with result or 404 if query not matched
-    async fn http_retrieve(Path(pk): Path<u64>) -> Result<Response> {
-        tracing::debug!("[{}] http retrive: pk: {pk}", Self::modle_name());
-        Ok(Json(
-            <T::Entity as EntityTrait>::find_by_id(Self::exchange_primary_key(pk))
-                .one(Self::get_db_connection().await)
-                .await
-                .context(OperateDatabaseSnafu)?
-                .context(PrimaryKeyNotFoundSnafu { pk })?,
+    /// build the list response, the body and headers depend on [`ModelViewExt::pagination_style`]
+    fn list_response<R: Serialize>(results: Vec<R>, meta: ListMeta, uri: &Uri) -> Response {
+        let page_num_param = Self::page_num_param();
+        match (Self::pagination_style(), meta) {
+            (
+                _,
+                ListMeta::Cursor {
+                    next_cursor,
+                    prev_cursor,
+                },
+            ) => Json(CursorPage {
+                next_cursor,
+                prev_cursor,
+                results,
+            })
+            .into_response(),
+            (PaginationStyle::Envelope, ListMeta::Offset(Some(page_info))) => Json(Page {
+                count: page_info.count,
+                next: page_info.next(uri, page_num_param),
+                previous: page_info.previous(uri, page_num_param),
+                results,
+            })
+            .into_response(),
+            (PaginationStyle::Headers, ListMeta::Offset(Some(page_info))) => {
+                (page_info.headers(uri, page_num_param), Json(results)).into_response()
+            }
+            _ => Json(results).into_response(),
+        }
+    }
+
+    /// relations which can be embedded into the responses of list and retrieve with /api?expand=teacher,courses
+    /// default is empty which disable expanding
+    fn expand_relations() -> Vec<Expand<Model<T>>> {
+        vec![]
+    }
+
+    /// max levels of nested relations like `courses.teacher` in expand query
+    fn max_expand_depth() -> usize {
+        2
+    }
+
+    #[inline]
+    fn expand_param() -> &'static str {
+        "expand"
+    }
+
+    /// parse the relations to expand from query string
+    /// return 400 if a relation is not in [`ModelViewExt::expand_relations`] or too deep
+    fn expand_trees(query: &Value) -> Result<Vec<ExpandTree>> {
+        match query.get(Self::expand_param()).and_then(Value::as_str) {
+            Some(raw) => {
+                expand::parse_expand(raw, &Self::expand_relations(), Self::max_expand_depth())
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// the outputs of `models` with the related models embedded
+    async fn expand_outputs(models: Vec<Model<T>>, trees: &[ExpandTree]) -> Result<Vec<Value>> {
+        let values = models
+            .iter()
+            .map(|model| serde_json::json!(S::Output::from(model.clone())))
+            .collect();
+        expand::expand_models(
+            &models,
+            values,
+            Self::get_db_connection().await,
+            trees,
+            &Self::expand_relations(),
         )
-        .into_response())
+        .await
     }
 
-    /// DELETE a instance with /api/:id
-    /// return http 204 if success delete or http 404 if not matched or http 500 with error info
-    async fn http_delete(Path(pk): Path<u64>) -> Result<StatusCode> {
-        let db = Self::get_db_connection().await;
-        tracing::debug!("[{}] http delete: pk: {pk}", Self::modle_name());
-        <T::Entity as EntityTrait>::find_by_id(Self::exchange_primary_key(pk))
-            .one(db)
-            .await
-            .context(OperateDatabaseSnafu)?
-            .context(PrimaryKeyNotFoundSnafu { pk })?
-            .delete(db)
-            .await
-            .context(OperateDatabaseSnafu)?;
-        tracing::debug!("[{}] http delete: success pk: {pk}", Self::modle_name());
-        Ok(StatusCode::NO_CONTENT)
+    fn expand_with_fields_error() -> Result<Response> {
+        InvalidFieldSnafu {
+            field: Self::expand_param(),
+            reason: format!(
+                "can not be used with `{}` or `{}`",
+                Self::fields_param(),
+                Self::exclude_param()
+            ),
+        }
+        .fail()
     }
 
-    async fn http_delete_all() -> Result<StatusCode> {
+    /// default return a bare json array from [`ModelViewExt::http_list`],
+    /// change into [`PaginationStyle::Envelope`] or [`PaginationStyle::Headers`] to get count and page links,
+    /// or [`PaginationStyle::Cursor`] for keyset pagination on large tables
+    fn pagination_style() -> PaginationStyle {
+        PaginationStyle::default()
+    }
+
+    #[inline]
+    fn cursor_param() -> &'static str {
+        "cursor"
+    }
+
+    /// ordering for cursor pagination, `?ordering` is rejected with 400 in cursor mode
+    /// default is [`ModelViewExt::default_ordering`], the primary keys are appended in the direction
+    /// of the first column to keep the ordering unique, every column must not be nullable
+    /// or the list returns 500, since the keyset comparisons skip the rows with `NULL`
+    fn cursor_ordering() -> Vec<(<T::Entity as EntityTrait>::Column, Order)> {
+        let mut ordering = Self::default_ordering();
+        let order = ordering
+            .first()
+            .map_or(Order::Desc, |(_, order)| order.clone());
+        for key in <T::Entity as EntityTrait>::PrimaryKey::iter() {
+            let column = key.into_column();
+            if !ordering.iter().any(|(c, _)| c.as_str() == column.as_str()) {
+                ordering.push((column, order.clone()));
+            }
+        }
+        ordering
+    }
+
+    /// GET a single query result with /api/:id
+    /// select only some columns with url like /api/:id?fields=id,name or /api/:id?exclude=region
+    /// embed related models with url like /api/:id?expand=teacher
+    /// return http 200 with result and the `ETag` of the instance, or the hash of the body if
+    /// it is projected or expanded, or 404 if query not matched,
+    /// or 304 if `If-None-Match` or `If-Modified-Since` shows the instance is not modified
+    async fn http_retrieve(
+        RequestParts(parts): RequestParts,
+        Path(pk): Path<PrimaryKeyValue<T>>,
+        Query(query): Query<Value>,
+    ) -> Result<Response> {
+        Self::has_permission(Action::Retrieve, &parts).await?;
+        Self::retrieve(pk, &query, &Scope::none(), &parts).await
+    }
+
+    /// retrieve an instance in the scope, return 404 if the instance is not in the scope
+    async fn retrieve(
+        pk: PrimaryKeyValue<T>,
+        query: &Value,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        parts: &Parts,
+    ) -> Result<Response> {
+        tracing::debug!("[{}] http retrive: pk: {pk:?}", Self::modle_name());
+        let expand = Self::expand_trees(query)?;
+        let projection = Self::field_projection(query)?;
+        if projection.is_some() && !expand.is_empty() {
+            return Self::expand_with_fields_error();
+        }
+        let include_deleted = Self::include_deleted(query, parts).await?;
         let db = Self::get_db_connection().await;
-        tracing::debug!("[{}] http delete all", Self::modle_name());
-        <T::Entity as EntityTrait>::delete_many()
-            .exec(db)
-            .await
-            .context(OperateDatabaseSnafu)?;
-        tracing::debug!("[{}] http delete all success", Self::modle_name());
+        let model = Self::find_scoped_instance(db, pk.clone(), scope, include_deleted).await?;
+        Self::has_object_permission(Action::Retrieve, parts, &model).await?;
+        let last_modified = Self::last_modified([&model]);
+        // the instance is loaded for the object permission even if only some columns are selected,
+        // the `ETag` of a projection or an expansion is the hash of the body,
+        // and the related models are not covered by the `Last-Modified` of the instance
+        let (response, last_modified) = match projection {
+            None if expand.is_empty() => {
+                let etag = HeaderValue::from_str(&Self::etag(&model)?)
+                    .ok()
+                    .context(InternalServerSnafu)?;
+                let mut response = Json(S::Output::from(model)).into_response();
+                response.headers_mut().insert(header::ETAG, etag);
+                (response, last_modified)
+            }
+            None => {
+                let value = Self::expand_outputs(vec![model], &expand).await?;
+                let value = value.into_iter().next().unwrap_or_default();
+                (Json(value).into_response(), None)
+            }
+            Some(columns) => {
+                let display = Self::primary_key_display(&pk);
+                let row = <T::Entity as EntityTrait>::find_by_id(pk)
+                    .filter(Self::scope_condition(scope, include_deleted))
+                    .select_only()
+                    .columns(columns)
+                    .into_json()
+                    .one(db)
+                    .await
+                    .context(OperateDatabaseSnafu)?
+                    .context(PrimaryKeyNotFoundSnafu { pk: display })?;
+                (Json(row).into_response(), last_modified)
+            }
+        };
+        Self::conditional_response(parts, response, last_modified).await
+    }
+
+    /// DELETE a instance with /api/:id, or mark it deleted if [`ModelViewExt::soft_delete`] is set
+    /// return http 204 if success delete or http 404 if not matched or http 500 with error info
+    async fn http_delete(
+        RequestParts(parts): RequestParts,
+        Path(pk): Path<PrimaryKeyValue<T>>,
+    ) -> Result<StatusCode> {
+        Self::has_permission(Action::Delete, &parts).await?;
+        Self::delete(pk, &Scope::none(), &parts).await
+    }
+
+    /// delete an instance in the scope, return 404 if the instance is not in the scope
+    async fn delete(
+        pk: PrimaryKeyValue<T>,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        parts: &Parts,
+    ) -> Result<StatusCode> {
+        tracing::debug!("[{}] http delete: pk: {pk:?}", Self::modle_name());
+        let txn = Self::begin().await?;
+        let model = Self::find_scoped_instance(&txn, pk.clone(), scope, false).await?;
+        Self::has_object_permission(Action::Delete, parts, &model).await?;
+        Self::check_if_match(parts, &model)?;
+        Self::before_delete(&model, parts, &txn).await?;
+        if let Some(soft_delete) = Self::soft_delete() {
+            let mut active_model: T = model.clone().into_active_model();
+            active_model.set(soft_delete.column(), soft_delete.deleted_value()?);
+            Self::save_update(active_model, &model, &txn).await?;
+        } else {
+            Self::save_delete(&model, &txn).await?;
+        }
+        Self::after_delete(&model, parts, &txn).await?;
+        txn.commit().await.context(OperateDatabaseSnafu)?;
+        tracing::debug!("[{}] http delete: success pk: {pk:?}", Self::modle_name());
         Ok(StatusCode::NO_CONTENT)
     }
 
-    /// change a value u64 into primary key
-    #[inline]
-    fn exchange_primary_key(
-        id: u64,
-    ) -> <<T::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType {
-        <<T::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType::try_from_u64(id)
-            .unwrap()
+    /// restore route like `/:id/restore`
+    fn restore_path() -> String {
+        format!("{}/restore", Self::detail_path())
     }
 
-    generate_by_params! {size, "size", 20}
-    generate_by_params! {num, "num", 0, 1}
+    /// POST /api/:id/restore to restore a soft deleted instance, only for the privileged callers
+    /// return http 200 with the restored instance, 404 if not matched or 403 if not privileged
+    async fn http_restore(
+        RequestParts(parts): RequestParts,
+        Path(pk): Path<PrimaryKeyValue<T>>,
+    ) -> Result<Json<S::Output>> {
+        Self::has_permission(Action::Restore, &parts).await?;
+        Self::has_privilege(&parts).await?;
+        Self::restore(pk, &Scope::none(), &parts).await
+    }
 
-    /// get http routers with full operates
-    fn http_router(nest_prefix: &'static str) -> Router
+    /// restore an instance in the scope, return 404 if the instance is not in the scope
+    async fn restore(
+        pk: PrimaryKeyValue<T>,
+        scope: &Scope<<T::Entity as EntityTrait>::Column>,
+        parts: &Parts,
+    ) -> Result<Json<S::Output>> {
+        tracing::debug!("[{}] http restore: pk: {pk:?}", Self::modle_name());
+        let display = Self::primary_key_display(&pk);
+        let soft_delete = Self::soft_delete().context(PrimaryKeyNotFoundSnafu { pk: display })?;
+        let txn = Self::begin().await?;
+        let model = Self::find_scoped_instance(&txn, pk, scope, true).await?;
+        Self::has_object_permission(Action::Restore, parts, &model).await?;
+        let mut active_model: T = model.clone().into_active_model();
+        active_model.set(soft_delete.column(), soft_delete.restored_value()?);
+        Self::before_update(&mut active_model, parts, &txn).await?;
+        let result = Self::save_update(active_model, &model, &txn).await?;
+        Self::after_update(&result, parts, &txn).await?;
+        txn.commit().await.context(OperateDatabaseSnafu)?;
+        Ok(Json(S::Output::from(result)))
+    }
+
+    /// max count of items in a bulk operation, return 400 if a batch is larger
+    fn max_batch_size() -> usize {
+        100
+    }
+
+    fn check_batch_size(size: usize) -> Result<()> {
+        let max = Self::max_batch_size();
+        ensure!(
+            size <= max,
+            InvalidBodySnafu {
+                reason: format!("batch size exceeds the max batch size {max}"),
+            }
+        );
+        Ok(())
+    }
+
+    /// route of the bulk create and bulk update, the static segment wins over the detail route,
+    /// so an instance whose single primary key is `bulk` can not be reached at `/api/bulk`,
+    /// override this like `/-/bulk` if such a primary key is possible
+    #[inline]
+    fn bulk_path() -> &'static str {
+        "/bulk"
+    }
+
+    /// POST a json array body to /api/bulk and create all the lines with `insert_many` in a transaction,
+    /// or one by one if the database not support `RETURNING`
+    /// return http 201 with the created instance of every item,
+    /// or http 400 with the error of every invalid item and nothing is created
+    async fn http_bulk_create(
+        RequestParts(parts): RequestParts,
+        JsonBody(items): JsonBody<Vec<Value>>,
+    ) -> Result<Response> {
+        Self::has_permission(Action::BulkCreate, &parts).await?;
+        Self::check_batch_size(items.len())?;
+        let txn = Self::begin().await?;
+        let mut results: Vec<BulkItemResult<S::Output>> = vec![];
+        let mut active_models = vec![];
+        for (index, item) in items.into_iter().enumerate() {
+            match from_json_value::<S::Create>(item) {
+                Ok(data) => {
+                    let mut active_model: T = data.into_active_model();
+                    if let Err(e) = Self::check_soft_delete_column(&active_model) {
+                        results.push(BulkItemResult::err(index, e));
+                        continue;
+                    }
+                    Self::unset_auto_increment_primary_key(&mut active_model);
+                    if let Err(e) = Self::before_create(&mut active_model, &parts, &txn).await {
+                        results.push(BulkItemResult::err(index, e));
+                        continue;
+                    }
+                    if let Err(e) = Self::validate(&active_model, Action::BulkCreate).await {
+                        results.push(BulkItemResult::err(index, e));
+                        continue;
+                    }
+                    active_models.push(active_model);
+                    results.push(BulkItemResult {
+                        index,
+                        instance: None,
+                        error: None,
+                    });
+                }
+                Err(e) => results.push(BulkItemResult::err(index, e)),
+            }
+        }
+        if results.iter().any(|result| result.error.is_some()) {
+            tracing::debug!("[{}] http bulk create: invalid items", Self::modle_name());
+            return Ok((StatusCode::BAD_REQUEST, Json(results)).into_response());
+        }
+        if !active_models.is_empty() {
+            if txn.support_returning() {
+                // every row of `insert_many` must set the same columns, so the rows are grouped by them
+                let mut groups: BTreeMap<Vec<bool>, (Vec<usize>, Vec<T>)> = BTreeMap::new();
+                for (position, active_model) in active_models.into_iter().enumerate() {
+                    let set = <T::Entity as EntityTrait>::Column::iter()
+                        .map(|column| !active_model.get(column).is_not_set())
+                        .collect::<Vec<_>>();
+                    let (positions, group) = groups.entry(set).or_default();
+                    positions.push(position);
+                    group.push(active_model);
+                }
+                let mut models = results.iter().map(|_| None).collect::<Vec<_>>();
+                for (positions, group) in groups.into_values() {
+                    let insert = <T::Entity as EntityTrait>::insert_many(group);
+                    let mut statement = insert.into_query();
+                    statement.returning_all();
+                    let inserted =
+                        Model::<T>::find_by_statement(txn.get_database_backend().build(&statement))
+                            .all(&txn)
+                            .await
+                            .context(OperateDatabaseSnafu)?;
+                    for (position, model) in positions.into_iter().zip(inserted) {
+                        models[position] = Some(model);
+                    }
+                }
+                for (result, model) in results.iter_mut().zip(models) {
+                    let model = model.context(InternalServerSnafu)?;
+                    Self::after_create(&model, &parts, &txn).await?;
+                    result.instance = Some(S::Output::from(model));
+                }
+            } else {
+                for (result, active_model) in results.iter_mut().zip(active_models) {
+                    let model = active_model
+                        .insert(&txn)
+                        .await
+                        .context(OperateDatabaseSnafu)?;
+                    Self::after_create(&model, &parts, &txn).await?;
+                    result.instance = Some(S::Output::from(model));
+                }
+            }
+        }
+        txn.commit().await.context(OperateDatabaseSnafu)?;
+        tracing::debug!(
+            "[{}] http bulk create: created {}",
+            Self::modle_name(),
+            results.len()
+        );
+        Ok((StatusCode::CREATED, Json(results)).into_response())
+    }
+
+    /// PATCH a json array body like `[{"pk": 1, "fields": {"name": "bob"}}]` to /api/bulk
+    /// and change all the lines in a transaction
+    /// return http 200 with the updated instance of every item,
+    /// or http 400 with the error of every invalid or not found item and nothing is changed
+    async fn http_bulk_update(
+        RequestParts(parts): RequestParts,
+        JsonBody(items): JsonBody<Vec<BulkUpdateItem<PrimaryKeyValue<T>>>>,
+    ) -> Result<Response> {
+        Self::has_permission(Action::BulkUpdate, &parts).await?;
+        Self::check_batch_size(items.len())?;
+        let txn = Self::begin().await?;
+        let mut results = vec![];
+        let mut failed = false;
+        for (index, item) in items.into_iter().enumerate() {
+            let display = Self::primary_key_display(&item.pk);
+            let model = <T::Entity as EntityTrait>::find_by_id(item.pk)
+                .filter(Self::scope_condition(&Scope::none(), false))
+                .one(&txn)
+                .await
+                .context(OperateDatabaseSnafu)?;
+            let Some(model) = model else {
+                failed = true;
+                results.push(BulkItemResult::err(
+                    index,
+                    PrimaryKeyNotFoundSnafu { pk: display }.build(),
+                ));
+                continue;
+            };
+            if let Err(e) = Self::has_object_permission(Action::BulkUpdate, &parts, &model).await {
+                failed = true;
+                results.push(BulkItemResult::err(index, e));
+                continue;
+            }
+            let mut active_model: T = model.clone().into_active_model();
+            if let Err(e) = Self::set_fields(&mut active_model, &model, &item.fields) {
+                failed = true;
+                results.push(BulkItemResult::err(index, e));
+                continue;
+            }
+            if let Err(e) = Self::before_update(&mut active_model, &parts, &txn).await {
+                failed = true;
+                results.push(BulkItemResult::err(index, e));
+                continue;
+            }
+            if let Err(e) = Self::validate(&active_model, Action::BulkUpdate).await {
+                failed = true;
+                results.push(BulkItemResult::err(index, e));
+                continue;
+            }
+            let model = if failed || !active_model.is_changed() {
+                model
+            } else {
+                let model = Self::save_update(active_model, &model, &txn).await?;
+                if let Err(e) = Self::after_update(&model, &parts, &txn).await {
+                    failed = true;
+                    results.push(BulkItemResult::err(index, e));
+                    continue;
+                }
+                model
+            };
+            results.push(BulkItemResult::ok(index, S::Output::from(model)));
+        }
+        if failed {
+            tracing::debug!("[{}] http bulk update: invalid items", Self::modle_name());
+            return Ok((StatusCode::BAD_REQUEST, Json(failed_results(results))).into_response());
+        }
+        txn.commit().await.context(OperateDatabaseSnafu)?;
+        tracing::debug!(
+            "[{}] http bulk update: updated {}",
+            Self::modle_name(),
+            results.len()
+        );
+        Ok(Json(results).into_response())
+    }
+
+    /// DELETE all the instances matched the filter and search query like /api?age__lt=18 in a transaction,
+    /// or mark them deleted if [`ModelViewExt::soft_delete`] is set
+    /// return http 200 with every deleted instance, http 400 without a filter or search,
+    /// or http 400 if more than [`ModelViewExt::max_batch_size`] instances are matched and nothing is deleted
+    async fn http_bulk_delete(
+        RequestParts(parts): RequestParts,
+        Query(query): Query<Value>,
+    ) -> Result<Response> {
+        Self::has_permission(Action::BulkDelete, &parts).await?;
+        let filter = Self::filter_condition(&query)?;
+        let search = Self::search_condition(&query);
+        ensure!(
+            !filter.is_empty() || search.is_some(),
+            InvalidBodySnafu {
+                reason: "bulk delete requires at least one filter or search",
+            }
+        );
+        let mut condition = filter.add(Self::scope_condition(&Scope::none(), false));
+        if let Some(search) = search {
+            condition = condition.add(search);
+        }
+        let txn = Self::begin().await?;
+        let models = <T::Entity as EntityTrait>::find()
+            .filter(condition.clone())
+            .limit((Self::max_batch_size() as u64).saturating_add(1))
+            .all(&txn)
+            .await
+            .context(OperateDatabaseSnafu)?;
+        Self::check_batch_size(models.len())?;
+        for model in &models {
+            Self::has_object_permission(Action::BulkDelete, &parts, model).await?;
+            Self::before_delete(model, &parts, &txn).await?;
+        }
+        if let (Some(soft_delete), ETagStyle::Version(_)) =
+            (Self::soft_delete(), Self::etag_style())
+        {
+            // bump the version of every instance like a single delete, 412 if one is changed meanwhile
+            for model in &models {
+                let mut active_model: T = model.clone().into_active_model();
+                active_model.set(soft_delete.column(), soft_delete.deleted_value()?);
+                Self::save_update(active_model, model, &txn).await?;
+            }
+        } else if let Some(soft_delete) = Self::soft_delete() {
+            <T::Entity as EntityTrait>::update_many()
+                .col_expr(
+                    soft_delete.column(),
+                    Expr::value(soft_delete.deleted_value()?),
+                )
+                .filter(condition)
+                .exec(&txn)
+                .await
+                .context(OperateDatabaseSnafu)?;
+        } else {
+            <T::Entity as EntityTrait>::delete_many()
+                .filter(condition)
+                .exec(&txn)
+                .await
+                .context(OperateDatabaseSnafu)?;
+        }
+        for model in &models {
+            Self::after_delete(model, &parts, &txn).await?;
+        }
+        txn.commit().await.context(OperateDatabaseSnafu)?;
+        tracing::debug!(
+            "[{}] http bulk delete: deleted {}",
+            Self::modle_name(),
+            models.len()
+        );
+        let results = models
+            .into_iter()
+            .enumerate()
+            .map(|(index, model)| BulkItemResult::ok(index, S::Output::from(model)))
+            .collect::<Vec<_>>();
+        Ok(Json(results).into_response())
+    }
+
+    /// find the parent and scope the instances to its children, return 404 if the parent not exists
+    async fn parent_scope<P, R>(
+        relation: R,
+        parent: P::Key,
+    ) -> Result<Scope<<T::Entity as EntityTrait>::Column>>
+    where
+        P: ParentEntity,
+        R: RelationTrait + Send,
+    {
+        let display = key_display(&parent);
+        P::find_by_key(parent.clone())
+            .one(Self::get_db_connection().await)
+            .await
+            .context(OperateDatabaseSnafu)?
+            .context(PrimaryKeyNotFoundSnafu { pk: display })?;
+        Scope::parent::<P, _>(relation.def(), parent)
+    }
+
+    /// GET the children of a parent with /api/parent/:parent_id/children, see [`ModelViewExt::http_list`]
+    async fn http_nested_list<P, R>(
+        State(relation): State<R>,
+        RequestParts(parts): RequestParts,
+        OriginalUri(uri): OriginalUri,
+        Path(parent): Path<P::Key>,
+        Query(query): Query<Value>,
+    ) -> Result<Response>
+    where
+        P: ParentEntity,
+        R: RelationTrait + Clone + Send + Sync + 'static,
+    {
+        Self::has_permission(Action::List, &parts).await?;
+        let scope = Self::parent_scope::<P, R>(relation, parent).await?;
+        Self::list(&uri, &query, &scope, &parts).await
+    }
+
+    /// POST a json body to /api/parent/:parent_id/children and create a child of the parent,
+    /// the foreign keys are set to the parent, see [`ModelViewExt::http_create`]
+    async fn http_nested_create<P, R>(
+        State(relation): State<R>,
+        RequestParts(parts): RequestParts,
+        OriginalUri(uri): OriginalUri,
+        Path(parent): Path<P::Key>,
+        JsonBody(data): JsonBody<S::Create>,
+    ) -> Result<Response>
+    where
+        P: ParentEntity,
+        R: RelationTrait + Clone + Send + Sync + 'static,
+    {
+        Self::has_permission(Action::Create, &parts).await?;
+        let scope = Self::parent_scope::<P, R>(relation, parent).await?;
+        Self::create(&uri, data, &scope, &parts).await
+    }
+
+    /// GET a child of a parent with /api/parent/:parent_id/children/:id, see [`ModelViewExt::http_retrieve`]
+    async fn http_nested_retrieve<P, R>(
+        State(relation): State<R>,
+        RequestParts(parts): RequestParts,
+        Path((parent, pk)): Path<(P::Key, PrimaryKeyValue<T>)>,
+        Query(query): Query<Value>,
+    ) -> Result<Response>
+    where
+        P: ParentEntity,
+        R: RelationTrait + Clone + Send + Sync + 'static,
+    {
+        Self::has_permission(Action::Retrieve, &parts).await?;
+        let scope = Self::parent_scope::<P, R>(relation, parent).await?;
+        Self::retrieve(pk, &query, &scope, &parts).await
+    }
+
+    /// PUT a json body to /api/parent/:parent_id/children/:id, see [`ModelViewExt::http_update`]
+    async fn http_nested_update<P, R>(
+        State(relation): State<R>,
+        RequestParts(parts): RequestParts,
+        Path((parent, pk)): Path<(P::Key, PrimaryKeyValue<T>)>,
+        JsonBody(data): JsonBody<S::Update>,
+    ) -> Result<Response>
+    where
+        P: ParentEntity,
+        R: RelationTrait + Clone + Send + Sync + 'static,
+    {
+        Self::has_permission(Action::Update, &parts).await?;
+        let scope = Self::parent_scope::<P, R>(relation, parent).await?;
+        Self::update(pk, data, &scope, &parts).await
+    }
+
+    /// PATCH a json object body to /api/parent/:parent_id/children/:id, see [`ModelViewExt::http_partial_update`]
+    async fn http_nested_partial_update<P, R>(
+        State(relation): State<R>,
+        RequestParts(parts): RequestParts,
+        Path((parent, pk)): Path<(P::Key, PrimaryKeyValue<T>)>,
+        JsonBody(data): JsonBody<Value>,
+    ) -> Result<Json<S::Output>>
+    where
+        P: ParentEntity,
+        R: RelationTrait + Clone + Send + Sync + 'static,
+    {
+        Self::has_permission(Action::PartialUpdate, &parts).await?;
+        let scope = Self::parent_scope::<P, R>(relation, parent).await?;
+        Self::partial_update(pk, data, &scope, &parts).await
+    }
+
+    /// DELETE a child of a parent with /api/parent/:parent_id/children/:id, see [`ModelViewExt::http_delete`]
+    async fn http_nested_delete<P, R>(
+        State(relation): State<R>,
+        RequestParts(parts): RequestParts,
+        Path((parent, pk)): Path<(P::Key, PrimaryKeyValue<T>)>,
+    ) -> Result<StatusCode>
+    where
+        P: ParentEntity,
+        R: RelationTrait + Clone + Send + Sync + 'static,
+    {
+        Self::has_permission(Action::Delete, &parts).await?;
+        let scope = Self::parent_scope::<P, R>(relation, parent).await?;
+        Self::delete(pk, &scope, &parts).await
+    }
+
+    /// prefix of the nested routes like `/api/teacher/:teacher_id/students`
+    /// panic if the parent or this entity has a composite primary key,
+    /// which can not be extracted from the `/:teacher_id/:id` path
+    fn nested_prefix<P: ParentEntity>(parent_prefix: &str, name: &str) -> String {
+        assert!(
+            <P as EntityTrait>::PrimaryKey::iter().count() == 1
+                && <T::Entity as EntityTrait>::PrimaryKey::iter().count() == 1,
+            "[{}] nested routes `{name}` do not support a composite primary key",
+            Self::modle_name()
+        );
+        format!(
+            "{}/:{}/{}",
+            parent_prefix.trim_end_matches('/'),
+            P::path_param(),
+            name.trim_matches('/')
+        )
+    }
+
+    generate_by_params! {size, "size", 20}
+    generate_by_params! {num, "num", 0, 1}
+
+    /// the largest `?page_size=` of a list, a larger one is lowered to it
+    fn max_page_size() -> u64 {
+        1000
+    }
+
+    /// custom endpoints besides the crud actions, mounted by [`ModelViewExt::http_router`],
+    /// a collection action can not be named `bulk` and a detail action `restore`, see [`ExtraAction`]
+    fn extra_actions() -> Vec<ExtraAction<T::Entity>> {
+        vec![]
+    }
+
+    /// run a detail extra action with the instance, return 404 if the instance not exists
+    async fn http_detail_action(
+        action: ExtraAction<T::Entity>,
+        parts: Parts,
+        pk: PrimaryKeyValue<T>,
+        query: Value,
+        body: Value,
+    ) -> Result<Response> {
+        Self::has_permission(action.action(), &parts).await?;
+        let ActionTarget::Detail(handler) = &action.target else {
+            return InternalServerSnafu.fail();
+        };
+        tracing::debug!("[{}] http {}: pk: {pk:?}", Self::modle_name(), action.name);
+        let model = Self::check_instance_exists(pk).await?;
+        Self::has_object_permission(action.action(), &parts, &model).await?;
+        handler(model, ActionRequest { parts, query, body }).await
+    }
+
+    /// run a collection extra action with the instances matched the filter and search query
+    async fn http_collection_action(
+        action: ExtraAction<T::Entity>,
+        parts: Parts,
+        query: Value,
+        body: Value,
+    ) -> Result<Response> {
+        Self::has_permission(action.action(), &parts).await?;
+        let ActionTarget::Collection(handler) = &action.target else {
+            return InternalServerSnafu.fail();
+        };
+        tracing::debug!("[{}] http {}", Self::modle_name(), action.name);
+        let include_deleted = Self::include_deleted(&query, &parts).await?;
+        let select = Self::filtered_select(&query, &Scope::none(), include_deleted)?;
+        handler(select, ActionRequest { parts, query, body }).await
+    }
+
+    /// path of an extra action, panic if it is the path of the bulk or restore action
+    /// like `/bulk` or `/:id/restore`
+    fn extra_action_path(action: &ExtraAction<T::Entity>, detail_path: &str) -> String {
+        let path = action.path(detail_path);
+        assert!(
+            path != Self::bulk_path() && path != Self::restore_path(),
+            "the extra action `{}` of {} is reserved by the route `{path}`, rename the action",
+            action.name,
+            Self::modle_name(),
+        );
+        path
+    }
+
+    /// routes of [`ModelViewExt::extra_actions`], the actions of a path are merged
+    /// panic if the method of an action is not supported by axum,
+    /// or the name of an action is reserved, see [`ModelViewExt::extra_action_path`]
+    fn extra_action_router() -> Router
     where
         Self: Send + 'static,
     {
-        Router::new().nest(
-            nest_prefix,
-            Router::new()
-                .route(
-                    "/:id",
-                    get(Self::http_retrieve)
-                        .put(Self::http_update)
-                        .delete(Self::http_delete),
-                )
-                .route(
-                    "/",
-                    get(Self::http_list)
-                        .post(Self::http_create)
-                        .delete(Self::http_delete_all),
+        let detail_path = Self::detail_path();
+        let mut routes: BTreeMap<String, MethodRouter> = BTreeMap::new();
+        for action in Self::extra_actions() {
+            let filter = MethodFilter::try_from(action.method.clone())
+                .expect("unsupported method of the extra action");
+            let path = Self::extra_action_path(&action, &detail_path);
+            let method_router = routes.remove(&path).unwrap_or_default();
+            let method_router = match action.target {
+                ActionTarget::Detail(_) => method_router.on(
+                    filter,
+                    move |RequestParts(parts): RequestParts,
+                          Path(pk): Path<PrimaryKeyValue<T>>,
+                          Query(query): Query<Value>,
+                          ActionBody(body): ActionBody| {
+                        Self::http_detail_action(action, parts, pk, query, body)
+                    },
+                ),
+                ActionTarget::Collection(_) => method_router.on(
+                    filter,
+                    move |RequestParts(parts): RequestParts,
+                          Query(query): Query<Value>,
+                          ActionBody(body): ActionBody| {
+                        Self::http_collection_action(action, parts, query, body)
+                    },
                 ),
+            };
+            routes.insert(path, method_router);
+        }
+        routes
+            .into_iter()
+            .fold(Router::new(), |router, (path, method_router)| {
+                router.route(&path, method_router)
+            })
+    }
+
+    /// the actions exposed by the routers, the routes of the other actions answer 405
+    /// with the `Allow` header of the enabled methods, default [`ActionSet::FULL`]
+    fn actions() -> ActionSet {
+        ActionSet::FULL
+    }
+
+    /// get http routers of the children scoped to a parent of entity `P`
+    /// like `/api/teacher/:teacher_id/students` and `/api/teacher/:teacher_id/students/:id`,
+    /// `relation` is the relation from this entity to the parent like `student::Relation::Teacher`,
+    /// which must reference the primary key of the parent,
+    /// panic if the parent or this entity has a composite primary key
+    /// return 404 if the parent not exists or the instance is not a child of the parent
+    fn nested_router<P, R>(parent_prefix: &str, name: &str, relation: R) -> Router
+    where
+        Self: Send + 'static,
+        P: ParentEntity,
+        R: RelationTrait + Clone + Send + Sync + 'static,
+    {
+        let actions = Self::actions();
+        let detail = actions.method_router::<MethodRouter<R>>(&[
+            (Action::Retrieve, |route| {
+                route.get(Self::http_nested_retrieve::<P, R>)
+            }),
+            (Action::Update, |route| {
+                route.put(Self::http_nested_update::<P, R>)
+            }),
+            (Action::PartialUpdate, |route| {
+                route.patch(Self::http_nested_partial_update::<P, R>)
+            }),
+            (Action::Delete, |route| {
+                route.delete(Self::http_nested_delete::<P, R>)
+            }),
+        ]);
+        let list = actions.method_router::<MethodRouter<R>>(&[
+            (Action::List, |route| {
+                route.get(Self::http_nested_list::<P, R>)
+            }),
+            (Action::Create, |route| {
+                route.post(Self::http_nested_create::<P, R>)
+            }),
+        ]);
+        let router = [(Self::detail_path(), detail), ("/".to_owned(), list)]
+            .into_iter()
+            .fold(Router::new(), |router, (path, method_router)| {
+                router.route(&path, method_router.unwrap_or_default())
+            });
+        Router::new().nest(
+            &Self::nested_prefix::<P>(parent_prefix, name),
+            router.with_state(relation),
         )
     }
+
+    /// get http routers with the operates of [`ModelViewExt::actions`]
+    fn http_router(nest_prefix: &'static str) -> Router
+    where
+        Self: Send + 'static,
+    {
+        let actions = Self::actions();
+        let restore = actions.method_router::<MethodRouter>(&[(Action::Restore, |route| {
+            route.post(Self::http_restore)
+        })]);
+        let detail = actions.method_router::<MethodRouter>(&[
+            (Action::Retrieve, |route| route.get(Self::http_retrieve)),
+            (Action::Update, |route| route.put(Self::http_update)),
+            (Action::PartialUpdate, |route| {
+                route.patch(Self::http_partial_update)
+            }),
+            (Action::Delete, |route| route.delete(Self::http_delete)),
+        ]);
+        let bulk = actions.method_router::<MethodRouter>(&[
+            (Action::BulkCreate, |route| {
+                route.post(Self::http_bulk_create)
+            }),
+            (Action::BulkUpdate, |route| {
+                route.patch(Self::http_bulk_update)
+            }),
+        ]);
+        let list = actions.method_router::<MethodRouter>(&[
+            (Action::List, |route| route.get(Self::http_list)),
+            (Action::Create, |route| route.post(Self::http_create)),
+            (Action::BulkDelete, |route| {
+                route.delete(Self::http_bulk_delete)
+            }),
+        ]);
+        let mut routes = vec![
+            (Self::detail_path(), detail),
+            (Self::bulk_path().to_owned(), bulk),
+            ("/".to_owned(), list),
+        ];
+        if Self::soft_delete().is_some() {
+            routes.push((Self::restore_path(), restore));
+        }
+        // an empty method router answers 405 with an empty `Allow` header
+        let router = routes
+            .into_iter()
+            .fold(Router::new(), |router, (path, method_router)| {
+                router.route(&path, method_router.unwrap_or_default())
+            });
+        Router::new().nest(nest_prefix, router.merge(Self::extra_action_router()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TestClient;
+    use crate::tests_cfg::{article, enrollment, note, tag};
+    use sea_orm::{
+        ActiveValue, DatabaseBackend, ExecResult, MockDatabase, MockDatabaseConnection,
+        MockDatabaseTrait, MockExecResult, QueryResult, Statement, Transaction,
+    };
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    thread_local! {
+        static MOCK_DB: Cell<Option<&'static DatabaseConnection>> = const { Cell::new(None) };
+    }
+
+    /// every test runs on its own thread, the views of the test use a fresh connection of `db`
+    fn use_mock_db(db: MockDatabase) {
+        use_connection(db.into_connection());
+    }
+
+    fn use_connection(db: DatabaseConnection) {
+        let db: &'static DatabaseConnection = Box::leak(Box::new(db));
+        MOCK_DB.with(|cell| cell.set(Some(db)));
+    }
+
+    /// mock database which records the executed statements and how the transactions end
+    #[derive(Debug)]
+    struct RecordedMock {
+        mock: MockDatabase,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockDatabaseTrait for RecordedMock {
+        fn execute(
+            &mut self,
+            counter: usize,
+            stmt: Statement,
+        ) -> std::result::Result<ExecResult, DbErr> {
+            self.log.lock().unwrap().push(stmt.sql.clone());
+            self.mock.execute(counter, stmt)
+        }
+
+        fn query(
+            &mut self,
+            counter: usize,
+            stmt: Statement,
+        ) -> std::result::Result<Vec<QueryResult>, DbErr> {
+            self.log.lock().unwrap().push(stmt.sql.clone());
+            self.mock.query(counter, stmt)
+        }
+
+        fn begin(&mut self) {
+            self.mock.begin();
+        }
+
+        fn commit(&mut self) {
+            self.log.lock().unwrap().push("COMMIT".to_owned());
+            self.mock.commit();
+        }
+
+        fn rollback(&mut self) {
+            self.log.lock().unwrap().push("ROLLBACK".to_owned());
+            self.mock.rollback();
+        }
+
+        fn drain_transaction_log(&mut self) -> Vec<Transaction> {
+            self.mock.drain_transaction_log()
+        }
+
+        fn get_database_backend(&self) -> DatabaseBackend {
+            self.mock.get_database_backend()
+        }
+
+        fn ping(&self) -> std::result::Result<(), DbErr> {
+            self.mock.ping()
+        }
+    }
+
+    /// like [`use_mock_db`], return the log of the statements and `COMMIT` or `ROLLBACK`
+    fn use_recorded_mock_db(db: MockDatabase) -> Arc<Mutex<Vec<String>>> {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mock = RecordedMock {
+            mock: db,
+            log: log.clone(),
+        };
+        use_connection(DatabaseConnection::MockDatabaseConnection(Arc::new(
+            MockDatabaseConnection::new(mock),
+        )));
+        log
+    }
+
+    fn mock_db() -> &'static DatabaseConnection {
+        MOCK_DB
+            .with(Cell::get)
+            .expect("call use_mock_db before the request")
+    }
+
+    fn postgres() -> MockDatabase {
+        MockDatabase::new(DatabaseBackend::Postgres)
+    }
+
+    struct TagView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel> for TagView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        fn max_batch_size() -> usize {
+            2
+        }
+    }
+
+    fn rust_tag() -> tag::Model {
+        tag::Model {
+            slug: "rust".to_owned(),
+            title: "Rust".to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_return_instance_and_location() {
+        let encoded = tag::Model {
+            slug: "c++/rust ü".to_owned(),
+            title: "C++".to_owned(),
+        };
+        use_mock_db(postgres().append_query_results([[rust_tag()], [encoded.clone()]]));
+        let client = TestClient::new(TagView::http_router("/api/tag"));
+        let res = client.post("/api/tag").json(&rust_tag()).send().await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[header::LOCATION], "/api/tag/rust");
+        assert_eq!(res.json::<tag::Model>().await, rust_tag());
+
+        let res = client.post("/api/tag").json(&encoded).send().await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "/api/tag/c%2B%2B%2Frust%20%C3%BC"
+        );
+    }
+
+    struct EnrollmentView;
+
+    impl ModelViewExt<enrollment::ActiveModel> for EnrollmentView {}
+
+    #[test]
+    #[should_panic(expected = "composite primary key")]
+    fn nested_router_reject_composite_primary_key() {
+        let _ = EnrollmentView::nested_router::<tag::Entity, _>(
+            "/api/tag",
+            "enrollments",
+            article::Relation::Tag,
+        );
+    }
+
+    struct TagSchema;
+
+    impl ViewSchema<tag::ActiveModel> for TagSchema {
+        type Create = tag::TagCreate;
+        type Update = tag::TagCreate;
+        type Output = tag::TagOutput;
+    }
+
+    struct TagSchemaView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel, TagSchema> for TagSchemaView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+    }
+
+    #[tokio::test]
+    async fn hash_etag_of_the_output() {
+        use_mock_db(postgres().append_query_results([[rust_tag()]]));
+        let client = TestClient::new(TagSchemaView::http_router("/api/tag"));
+        let res = client.get("/api/tag/rust").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[header::ETAG],
+            hash_etag(br#"{"slug":"rust"}"#).as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn sparse_fields_only_select_output() {
+        let slug = || BTreeMap::from([("slug", sea_orm::Value::from("rust"))]);
+        let log = use_recorded_mock_db(
+            postgres()
+                .append_query_results([[slug()]])
+                .append_query_results([[rust_tag()]])
+                .append_query_results([[slug()]]),
+        );
+        let client = TestClient::new(TagSchemaView::http_router("/api/tag"));
+        // the fields are checked against the output before any query
+        let res = client.get("/api/tag?fields=title").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client.get("/api/tag/rust?exclude=title").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(log.lock().unwrap().is_empty());
+
+        let res = client.get("/api/tag?fields=slug").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.json::<Value>().await,
+            serde_json::json!([{"slug": "rust"}])
+        );
+        let res = client.get("/api/tag/rust?fields=slug").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.json::<Value>().await,
+            serde_json::json!({"slug": "rust"})
+        );
+        let log = log.lock().unwrap();
+        assert!(log[0].starts_with(r#"SELECT "tag"."slug" FROM "tag""#));
+        assert!(log[1].starts_with(r#"SELECT "tag"."slug", "tag"."title" FROM "tag""#));
+        assert!(log[2].starts_with(r#"SELECT "tag"."slug" FROM "tag""#));
+    }
+
+    struct ReadOnlyTagView;
+
+    impl ModelViewExt<tag::ActiveModel> for ReadOnlyTagView {
+        fn permissions() -> Vec<Box<dyn Permission<tag::Model>>> {
+            vec![
+                Box::new(crate::views::permission::IsAuthenticated),
+                Box::new(crate::views::permission::ReadOnly),
+            ]
+        }
+    }
+
+    #[tokio::test]
+    async fn deny_request_without_permission() {
+        let client = TestClient::new(ReadOnlyTagView::http_router("/api/tag"));
+        let res = client.get("/api/tag").send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = client.delete("/api/tag/rust").send().await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let router = ReadOnlyTagView::http_router("/api/tag").layer(axum::Extension(
+            crate::views::permission::Identity {
+                subject: "bob".to_owned(),
+            },
+        ));
+        let client = TestClient::new(router);
+        let res = client.post("/api/tag").json(&rust_tag()).send().await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    struct ValidatedTagView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel> for ValidatedTagView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        async fn validate(active_model: &tag::ActiveModel, _action: Action) -> Result<()> {
+            let mut errors = crate::views::ValidationErrors::new();
+            if let ActiveValue::Set(title) | ActiveValue::Unchanged(title) = &active_model.title {
+                errors.check(!title.is_empty(), "title", "must not be empty");
+            }
+            errors.into_result()
+        }
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_body_with_field_errors() {
+        use_mock_db(postgres());
+        let client = TestClient::new(ValidatedTagView::http_router("/api/tag"));
+        let res = client
+            .post("/api/tag")
+            .json(&serde_json::json!({"slug": "rust", "title": ""}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            res.json::<Value>().await["errors"],
+            serde_json::json!({"title": ["must not be empty"]})
+        );
+
+        let res = client
+            .post("/api/tag")
+            .json(&serde_json::json!({"slug": "rust", "title": 1}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(res.json::<Value>().await["errors"]["title"].is_array());
+
+        let res = client
+            .post("/api/tag")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body("{")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(res.json::<Value>().await["message"].is_string());
+    }
+
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+    struct HookedTagView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel> for HookedTagView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        async fn before_create(
+            active_model: &mut tag::ActiveModel,
+            _parts: &Parts,
+            _txn: &DatabaseTransaction,
+        ) -> Result<()> {
+            if let ActiveValue::Set(slug) | ActiveValue::Unchanged(slug) = &active_model.slug {
+                ensure!(
+                    slug != "admin",
+                    crate::error::ForbiddenSnafu {
+                        reason: "the slug is reserved",
+                    }
+                );
+            }
+            Ok(())
+        }
+
+        async fn after_create(
+            _model: &tag::Model,
+            _parts: &Parts,
+            _txn: &DatabaseTransaction,
+        ) -> Result<()> {
+            CREATED.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn lifecycle_hooks_around_create() {
+        use_mock_db(postgres().append_query_results([[rust_tag()]]));
+        let client = TestClient::new(HookedTagView::http_router("/api/tag"));
+        let res = client
+            .post("/api/tag")
+            .json(&serde_json::json!({"slug": "admin", "title": "Admin"}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(CREATED.load(Ordering::SeqCst), 0);
+
+        let res = client.post("/api/tag").json(&rust_tag()).send().await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(CREATED.load(Ordering::SeqCst), 1);
+
+        // without `RETURNING` the bulk create inserts and selects the rows one by one
+        use_mock_db(
+            MockDatabase::new(DatabaseBackend::MySql)
+                .append_exec_results([MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                }])
+                .append_query_results([[rust_tag()]]),
+        );
+        let res = client
+            .post("/api/tag/bulk")
+            .json(&[rust_tag()])
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let results = res.json::<Vec<BulkItemResult<tag::Model>>>().await;
+        assert_eq!(results[0].instance, Some(rust_tag()));
+        assert_eq!(CREATED.load(Ordering::SeqCst), 2);
+    }
+
+    struct SlugTagView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel, TagSchema> for SlugTagView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        async fn before_create(
+            active_model: &mut tag::ActiveModel,
+            _parts: &Parts,
+            _txn: &DatabaseTransaction,
+        ) -> Result<()> {
+            if active_model.title.as_ref() == "Rust" {
+                active_model.slug = ActiveValue::Set("rust".to_owned());
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn bulk_create_items_with_different_columns() {
+        let go = tag::Model {
+            slug: "go".to_owned(),
+            title: "Go".to_owned(),
+        };
+        // the tag without a slug is inserted first as its group sorts first
+        let log = use_recorded_mock_db(postgres().append_query_results([[go], [rust_tag()]]));
+        let client = TestClient::new(SlugTagView::http_router("/api/tag"));
+        let res = client
+            .post("/api/tag/bulk")
+            .json(&serde_json::json!([{"title": "Rust"}, {"title": "Go"}]))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(
+            res.json::<Value>().await,
+            serde_json::json!([
+                {"index": 0, "instance": {"slug": "rust"}, "error": null},
+                {"index": 1, "instance": {"slug": "go"}, "error": null},
+            ])
+        );
+        let log = log.lock().unwrap();
+        assert!(log[0].starts_with(r#"INSERT INTO "tag" ("title") VALUES ($1)"#));
+        assert!(log[1].starts_with(r#"INSERT INTO "tag" ("slug", "title") VALUES ($1, $2)"#));
+        assert_eq!(log[2], "COMMIT");
+    }
+
+    struct GuardedTagView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel> for GuardedTagView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        async fn before_update(
+            _active_model: &mut tag::ActiveModel,
+            _parts: &Parts,
+            _txn: &DatabaseTransaction,
+        ) -> Result<()> {
+            crate::error::ForbiddenSnafu {
+                reason: "tags are frozen",
+            }
+            .fail()
+        }
+
+        async fn before_delete(
+            _model: &tag::Model,
+            _parts: &Parts,
+            _txn: &DatabaseTransaction,
+        ) -> Result<()> {
+            crate::error::ForbiddenSnafu {
+                reason: "tags are frozen",
+            }
+            .fail()
+        }
+    }
+
+    #[tokio::test]
+    async fn update_and_delete_hooks_roll_back() {
+        let log =
+            use_recorded_mock_db(postgres().append_query_results([[rust_tag()], [rust_tag()]]));
+        let client = TestClient::new(GuardedTagView::http_router("/api/tag"));
+        let res = client
+            .patch("/api/tag/rust")
+            .json(&serde_json::json!({"title": "Go"}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = client.delete("/api/tag/rust").send().await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 4);
+        assert!(log[0].starts_with("SELECT") && log[2].starts_with("SELECT"));
+        assert_eq!((log[1].as_str(), log[3].as_str()), ("ROLLBACK", "ROLLBACK"));
+    }
+
+    struct NoteView;
+
+    #[async_trait]
+    impl ModelViewExt<note::ActiveModel> for NoteView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        fn soft_delete() -> Option<SoftDelete<note::Column>> {
+            Some(SoftDelete::Timestamp(note::Column::DeletedAt))
+        }
+    }
+
+    fn note() -> note::Model {
+        note::Model {
+            id: 1,
+            content: "todo".to_owned(),
+            is_archived: false,
+            version: 1,
+            deleted_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn soft_delete_and_deny_deleted_access() {
+        let deleted = note::Model {
+            deleted_at: Some(Default::default()),
+            ..note()
+        };
+        // the delete only finds and updates the note, never executes `DELETE`
+        let log = use_recorded_mock_db(
+            postgres()
+                .append_query_results([[note()], [deleted]])
+                .append_query_results([Vec::<note::Model>::new(), vec![]]),
+        );
+        let client = TestClient::new(NoteView::http_router("/api/note"));
+        let res = client.delete("/api/note/1").send().await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert!(log
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|sql| !sql.starts_with("DELETE")));
+
+        let res = client.get("/api/note").send().await;
+        assert_eq!(res.json::<Value>().await, serde_json::json!([]));
+        let res = client.get("/api/note/1").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let log = log.lock().unwrap().clone();
+        let reads = &log[log.len() - 2..];
+        assert!(reads
+            .iter()
+            .all(|sql| sql.contains(r#""note"."deleted_at" IS NULL"#)));
+
+        let res = client.get("/api/note?include_deleted=true").send().await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = client.get("/api/note/1?include_deleted=true").send().await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = client.post("/api/note/1/restore").send().await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = TestClient::new(TagView::http_router("/api/tag"))
+            .post("/api/tag/rust/restore")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn soft_delete_column_only_changed_by_delete() {
+        use_mock_db(postgres().append_query_results([[note()], [note()]]));
+        let client = TestClient::new(NoteView::http_router("/api/note"));
+        let deleted_at = "2024-01-01T00:00:00+00:00";
+        let res = client
+            .post("/api/note")
+            .json(&serde_json::json!({"id": 2, "content": "todo", "is_archived": false, "version": 1, "deleted_at": deleted_at}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client
+            .patch("/api/note/1")
+            .json(&serde_json::json!({"deleted_at": deleted_at}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client
+            .put("/api/note/1")
+            .json(&serde_json::json!({"id": 1, "content": "todo", "is_archived": false, "version": 1, "deleted_at": deleted_at}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    struct NoteSchema;
+
+    impl ViewSchema<note::ActiveModel> for NoteSchema {
+        type Create = note::Model;
+        type Update = note::NoteUpdate;
+        type Output = note::Model;
+    }
+
+    struct NoteSchemaView;
+
+    #[async_trait]
+    impl ModelViewExt<note::ActiveModel, NoteSchema> for NoteSchemaView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+    }
+
+    #[tokio::test]
+    async fn partial_update_only_write_update_schema() {
+        let updated = note::Model {
+            content: "done".to_owned(),
+            ..note()
+        };
+        use_mock_db(postgres().append_query_results([
+            [note()],
+            [note()],
+            [note()],
+            [updated.clone()],
+        ]));
+        let client = TestClient::new(NoteSchemaView::http_router("/api/note"));
+        let res = client
+            .patch("/api/note/1")
+            .json(&serde_json::json!({"is_archived": true}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client
+            .patch("/api/note/1")
+            .json(&serde_json::json!({"content": 1}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client
+            .patch("/api/note/1")
+            .json(&serde_json::json!({"content": "done"}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.json::<note::Model>().await, updated);
+    }
+
+    struct VersionedNoteView;
+
+    #[async_trait]
+    impl ModelViewExt<note::ActiveModel> for VersionedNoteView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        fn etag_style() -> ETagStyle<note::Column> {
+            ETagStyle::Version(note::Column::Version)
+        }
+
+        fn soft_delete() -> Option<SoftDelete<note::Column>> {
+            Some(SoftDelete::Timestamp(note::Column::DeletedAt))
+        }
+
+        fn filter_fields() -> Vec<note::Column> {
+            vec![note::Column::Content]
+        }
+    }
+
+    #[tokio::test]
+    async fn update_with_if_match_version() {
+        let updated = note::Model {
+            version: 2,
+            ..note()
+        };
+        // the last update matches no line as the version is changed meanwhile
+        use_mock_db(postgres().append_query_results([
+            vec![note()],
+            vec![note()],
+            vec![note()],
+            vec![updated],
+            vec![note()],
+            vec![],
+            vec![note()],
+            vec![note()],
+        ]));
+        let client = TestClient::new(VersionedNoteView::http_router("/api/note"));
+        let res = client.get("/api/note/1").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::ETAG], r#""1""#);
+
+        let patch = |etag: &'static str| {
+            client
+                .patch("/api/note/1")
+                .header(header::IF_MATCH, etag)
+                .json(&serde_json::json!({"content": "done"}))
+        };
+        let res = patch(r#""0""#).send().await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        let res = patch(r#""1""#).send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.json::<note::Model>().await.version, 2);
+        let res = patch(r#""1""#).send().await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        // a projection has the hash of its body instead of the version
+        let res = client.get("/api/note/1?fields=content").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_ne!(res.headers()[header::ETAG], r#""1""#);
+        assert_eq!(res.headers()[header::ETAG].len(), 34);
+    }
+
+    #[tokio::test]
+    async fn bulk_soft_delete_bump_version() {
+        let deleted = note::Model {
+            version: 2,
+            deleted_at: Some(Default::default()),
+            ..note()
+        };
+        let log = use_recorded_mock_db(postgres().append_query_results([[note()], [deleted]]));
+        let client = TestClient::new(VersionedNoteView::http_router("/api/note"));
+        let res = client.delete("/api/note?content=todo").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let log = log.lock().unwrap();
+        assert!(log[1].starts_with(r#"UPDATE "note" SET "version" = $1, "deleted_at" = $2"#));
+        assert!(log[1].contains(r#"AND "note"."version" = $4"#));
+        assert_eq!(log[2], "COMMIT");
+    }
+
+    struct CachedTagView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel> for CachedTagView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        fn cache_control() -> Option<HeaderValue> {
+            Some(HeaderValue::from_static("private, max-age=5"))
+        }
+    }
+
+    #[tokio::test]
+    async fn list_not_modified_with_if_none_match() {
+        use_mock_db(postgres().append_query_results([[rust_tag()], [rust_tag()]]));
+        let client = TestClient::new(CachedTagView::http_router("/api/tag"));
+        let res = client.get("/api/tag?page_size=0").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "private, max-age=5");
+        let etag = res.headers()[header::ETAG].clone();
+
+        let res = client
+            .get("/api/tag?page_size=0")
+            .header(header::IF_NONE_MATCH, etag.clone())
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers()[header::ETAG], etag);
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "private, max-age=5");
+        assert!(res.text().await.is_empty());
+    }
+
+    struct TimedNoteView;
+
+    #[async_trait]
+    impl ModelViewExt<note::ActiveModel> for TimedNoteView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        fn updated_at_column() -> Option<note::Column> {
+            Some(note::Column::DeletedAt)
+        }
+    }
+
+    #[tokio::test]
+    async fn list_last_modified_is_the_latest_of_the_page() {
+        let at = |secs: i64| {
+            sea_orm::prelude::ChronoDateTimeUtc::from_timestamp(secs, 0)
+                .unwrap()
+                .fixed_offset()
+        };
+        let notes = [
+            note::Model {
+                deleted_at: Some(at(1_700_000_000)),
+                ..note()
+            },
+            note::Model {
+                id: 2,
+                deleted_at: Some(at(1_700_000_100)),
+                ..note()
+            },
+        ];
+        // the drivers select a timestamp into json as a rfc 3339 string
+        let row = |id: i32, secs: i64| {
+            BTreeMap::from([
+                ("content", sea_orm::Value::from("todo")),
+                ("deleted_at", sea_orm::Value::from(at(secs).to_rfc3339())),
+                ("id", sea_orm::Value::from(id)),
+            ])
+        };
+        let log = use_recorded_mock_db(
+            postgres()
+                .append_query_results([notes])
+                .append_query_results([[row(1, 1_700_000_200), row(2, 1_700_000_000)]]),
+        );
+        let client = TestClient::new(TimedNoteView::http_router("/api/note"));
+        let res = client.get("/api/note").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[header::LAST_MODIFIED],
+            "Tue, 14 Nov 2023 22:15:00 GMT"
+        );
+
+        // the updated at column is selected for the header but left out of the body
+        let res = client.get("/api/note?fields=content").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[header::LAST_MODIFIED],
+            "Tue, 14 Nov 2023 22:16:40 GMT"
+        );
+        assert_eq!(
+            res.json::<Value>().await,
+            serde_json::json!([{"id": 1, "content": "todo"}, {"id": 2, "content": "todo"}])
+        );
+        assert!(log.lock().unwrap()[1].contains(r#", "note"."deleted_at" FROM "note""#));
+    }
+
+    struct CursorTagView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel> for CursorTagView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        fn pagination_style() -> PaginationStyle {
+            PaginationStyle::Cursor
+        }
+    }
+
+    #[tokio::test]
+    async fn cursor_pagination_reject_ordering() {
+        use_mock_db(postgres().append_query_results([[rust_tag()], [rust_tag()]]));
+        let client = TestClient::new(CursorTagView::http_router("/api/tag"));
+        let res = client.get("/api/tag?ordering=title").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client.get("/api/tag").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.json::<Value>().await;
+        assert_eq!(body["results"][0]["slug"], "rust");
+        // the huge page size is lowered to the max instead of overflowing
+        let res = client
+            .get(&format!("/api/tag?page_size={}", u64::MAX))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    struct ReadOnlyActionsTagView;
+
+    impl ModelViewExt<tag::ActiveModel> for ReadOnlyActionsTagView {
+        fn actions() -> ActionSet {
+            ActionSet::READ_ONLY
+        }
+    }
+
+    struct CreateOnlyTagView;
+
+    impl ModelViewExt<tag::ActiveModel> for CreateOnlyTagView {
+        fn actions() -> ActionSet {
+            ActionSet::EMPTY.with(Action::Create)
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_actions_answer_method_not_allowed() {
+        let client = TestClient::new(ReadOnlyActionsTagView::http_router("/api/tag"));
+        let res = client.delete("/api/tag").send().await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "GET,HEAD");
+        let res = client.put("/api/tag/rust").json(&rust_tag()).send().await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "GET,HEAD");
+        let res = client
+            .post("/api/tag/bulk")
+            .json(&[rust_tag()])
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "");
+
+        let client = TestClient::new(CreateOnlyTagView::http_router("/api/tag"));
+        let res = client.get("/api/tag").send().await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "POST");
+        let res = client.get("/api/tag/rust").send().await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "");
+    }
+
+    struct ActionTagView;
+
+    #[async_trait]
+    impl ModelViewExt<tag::ActiveModel> for ActionTagView {
+        async fn get_db_connection() -> &'static DatabaseConnection {
+            mock_db()
+        }
+
+        fn filter_fields() -> Vec<tag::Column> {
+            vec![tag::Column::Title]
+        }
+
+        fn extra_actions() -> Vec<ExtraAction<tag::Entity>> {
+            vec![
+                ExtraAction::detail(
+                    axum::http::Method::POST,
+                    "shout",
+                    |model: tag::Model, request| async move {
+                        let suffix = request.body["suffix"].as_str().unwrap_or_default();
+                        Ok(format!("{}{suffix}", model.title.to_uppercase()).into_response())
+                    },
+                ),
+                ExtraAction::collection(
+                    axum::http::Method::GET,
+                    "sql",
+                    |select: Select<tag::Entity>, _request| async move {
+                        Ok(select
+                            .build(DatabaseBackend::Postgres)
+                            .to_string()
+                            .into_response())
+                    },
+                ),
+            ]
+        }
+    }
+
+    struct ReservedActionTagView;
+
+    impl ModelViewExt<tag::ActiveModel> for ReservedActionTagView {
+        fn extra_actions() -> Vec<ExtraAction<tag::Entity>> {
+            vec![ExtraAction::collection(
+                axum::http::Method::GET,
+                "bulk",
+                |_select, _request| async move { Ok(StatusCode::OK.into_response()) },
+            )]
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the extra action `bulk` of reservedactiontagview is reserved")]
+    fn extra_action_reject_reserved_name() {
+        let _ = ReservedActionTagView::http_router("/api/tag");
+    }
+
+    #[tokio::test]
+    async fn extra_actions_receive_model_and_query() {
+        use_mock_db(postgres().append_query_results([[rust_tag()]]));
+        let client = TestClient::new(ActionTagView::http_router("/api/tag"));
+        let res = client
+            .post("/api/tag/rust/shout")
+            .json(&serde_json::json!({"suffix": "!"}))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await, "RUST!");
+
+        let res = client.get("/api/tag/sql?title=Rust").send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res
+            .text()
+            .await
+            .ends_with(r#"WHERE "tag"."title" = 'Rust'"#));
+        let res = client.post("/api/tag/sql").send().await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn filter_fields_are_opt_in() {
+        use_mock_db(postgres());
+        let client = TestClient::new(TagView::http_router("/api/tag"));
+        let res = client.get("/api/tag?title__startswith=R").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn bulk_delete_require_filter() {
+        use_mock_db(postgres());
+        let client = TestClient::new(TagView::http_router("/api/tag"));
+        let res = client.delete("/api/tag").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = client.delete("/api/tag?page_size=10").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn bulk_create_reject_invalid_batch() {
+        use_mock_db(postgres());
+        let client = TestClient::new(TagView::http_router("/api/tag"));
+        let res = client
+            .post("/api/tag/bulk")
+            .json(&serde_json::json!([rust_tag(), {"slug": 1}]))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let results = res.json::<Vec<BulkItemResult<tag::Model>>>().await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.instance.is_none()));
+        assert!(results[0].error.is_none());
+        assert!(results[1].error.is_some());
+
+        let res = client
+            .post("/api/tag/bulk")
+            .json(&[rust_tag(), rust_tag(), rust_tag()])
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
 }
@@ -0,0 +1,57 @@
+use sea_orm::{ColumnTrait, Order};
+
+use crate::error::{InvalidFieldSnafu, Result};
+
+/// prefix of a field in ordering query means descending, like `-create_time`
+pub const DESCENDING_PREFIX: char = '-';
+
+/// parse ordering query like `-create_time,name` into columns with order
+/// every field must be one of `fields`, or return 400
+pub fn parse_ordering<C: ColumnTrait>(raw: &str, fields: &[C]) -> Result<Vec<(C, Order)>> {
+    let mut ordering = vec![];
+    for item in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let (name, order) = match item.strip_prefix(DESCENDING_PREFIX) {
+            Some(name) => (name, Order::Desc),
+            None => (item, Order::Asc),
+        };
+        let column = fields
+            .iter()
+            .find(|column| column.as_str() == name)
+            .copied();
+        let Some(column) = column else {
+            return InvalidFieldSnafu {
+                field: name,
+                reason: "ordering is not allowed on this field",
+            }
+            .fail();
+        };
+        ordering.push((column, order));
+    }
+    Ok(ordering)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::tests_cfg::cake;
+    use sea_orm::{DbBackend, EntityTrait, Iterable, QueryOrder, QueryTrait};
+
+    #[test]
+    fn query_into_ordering() {
+        let fields = cake::Column::iter().collect::<Vec<_>>();
+        let mut select = cake::Entity::find();
+        for (column, order) in parse_ordering(" -name, id,", &fields).unwrap() {
+            select = select.order_by(column, order);
+        }
+        assert_eq!(
+            select.build(DbBackend::Postgres).to_string(),
+            r#"SELECT "cake"."id", "cake"."name" FROM "cake" ORDER BY "cake"."name" DESC, "cake"."id" ASC"#
+        );
+        assert!(parse_ordering("-unknown", &fields).is_err());
+        assert!(parse_ordering("name", &[cake::Column::Id]).is_err());
+    }
+}
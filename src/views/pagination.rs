@@ -0,0 +1,140 @@
+use axum::http::{header, HeaderMap, HeaderValue, Uri};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// header name for the total count of results with [`PaginationStyle::Headers`]
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+/// how `http_list` return the paginated results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaginationStyle {
+    /// return a bare json array of results
+    #[default]
+    Plain,
+    /// return a json object like `{"count": 10, "next": "...", "previous": null, "results": []}`
+    Envelope,
+    /// return a bare json array of results with `X-Total-Count` and RFC 8288 `Link` headers
+    Headers,
+    /// keyset pagination with `?cursor=&page_size=` ordered by the view default ordering,
+    /// return a json object like `{"next_cursor": "...", "prev_cursor": null, "results": []}`
+    Cursor,
+}
+
+/// paginated results envelope
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Page<M> {
+    /// count of all the matched results
+    pub count: u64,
+    /// url for the next page, null if this is the last page
+    pub next: Option<String>,
+    /// url for the previous page, null if this is the first page
+    pub previous: Option<String>,
+    pub results: Vec<M>,
+}
+
+/// how a fetched list is paginated, used to build the response of `http_list`
+#[derive(Debug, Clone)]
+pub enum ListMeta {
+    Cursor {
+        next_cursor: Option<String>,
+        prev_cursor: Option<String>,
+    },
+    /// `None` if the count is not fetched
+    Offset(Option<PageInfo>),
+}
+
+/// position of a fetched page used to build links
+#[derive(Debug, Clone, Copy)]
+pub struct PageInfo {
+    /// count of all the matched results
+    pub count: u64,
+    /// page index start from 0
+    pub index: u64,
+    /// count of all the pages, 0 if there are no results
+    pub pages: u64,
+}
+
+impl PageInfo {
+    /// url for the page index with the same path and other query params as `uri`
+    /// page number in url start from 1
+    pub fn page_url(uri: &Uri, page_num_param: &str, index: u64) -> String {
+        let mut pairs = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty() && pair.split('=').next() != Some(page_num_param))
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+        pairs.push(format!("{page_num_param}={}", index + 1));
+        format!("{}?{}", uri.path(), pairs.join("&"))
+    }
+
+    pub fn next(&self, uri: &Uri, page_num_param: &str) -> Option<String> {
+        (self.index + 1 < self.pages).then(|| Self::page_url(uri, page_num_param, self.index + 1))
+    }
+
+    pub fn previous(&self, uri: &Uri, page_num_param: &str) -> Option<String> {
+        (self.index > 0 && self.pages > 0)
+            .then(|| Self::page_url(uri, page_num_param, (self.index - 1).min(self.pages - 1)))
+    }
+
+    /// `X-Total-Count` and `Link` headers with `first`, `prev`, `next` and `last` relations
+    pub fn headers(&self, uri: &Uri, page_num_param: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(self.count));
+        if self.pages == 0 {
+            return headers;
+        }
+        let mut links = vec![format!(
+            r#"<{}>; rel="first""#,
+            Self::page_url(uri, page_num_param, 0)
+        )];
+        if let Some(previous) = self.previous(uri, page_num_param) {
+            links.push(format!(r#"<{previous}>; rel="prev""#));
+        }
+        if let Some(next) = self.next(uri, page_num_param) {
+            links.push(format!(r#"<{next}>; rel="next""#));
+        }
+        links.push(format!(
+            r#"<{}>; rel="last""#,
+            Self::page_url(uri, page_num_param, self.pages - 1)
+        ));
+        if let Ok(value) = HeaderValue::from_str(&links.join(", ")) {
+            headers.insert(header::LINK, value);
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_links() {
+        let uri = "/api/student?page_size=3&page_num=2&age__gte=18"
+            .parse::<Uri>()
+            .unwrap();
+        let info = PageInfo {
+            count: 7,
+            index: 1,
+            pages: 3,
+        };
+        assert_eq!(
+            info.next(&uri, "page_num").unwrap(),
+            "/api/student?page_size=3&age__gte=18&page_num=3"
+        );
+        assert_eq!(
+            info.previous(&uri, "page_num").unwrap(),
+            "/api/student?page_size=3&age__gte=18&page_num=1"
+        );
+        let headers = info.headers(&uri, "page_num");
+        assert_eq!(headers[TOTAL_COUNT_HEADER], "7");
+        assert_eq!(
+            headers[header::LINK],
+            r#"</api/student?page_size=3&age__gte=18&page_num=1>; rel="first", </api/student?page_size=3&age__gte=18&page_num=1>; rel="prev", </api/student?page_size=3&age__gte=18&page_num=3>; rel="next", </api/student?page_size=3&age__gte=18&page_num=3>; rel="last""#
+        );
+        let last = PageInfo { index: 2, ..info };
+        assert!(last.next(&uri, "page_num").is_none());
+    }
+}
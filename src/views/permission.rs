@@ -0,0 +1,169 @@
+use aide::OperationInput;
+use async_trait::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use snafu::ensure;
+use std::convert::Infallible;
+
+use crate::error::{ForbiddenSnafu, Result, UnauthorizedSnafu};
+
+/// the action of a request to a model view, more actions may be added
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Action {
+    List,
+    Retrieve,
+    Create,
+    Update,
+    PartialUpdate,
+    Delete,
+    BulkCreate,
+    BulkUpdate,
+    BulkDelete,
+    Restore,
+    /// an extra action of the view, safe if it is requested with `GET` or `HEAD`,
+    /// see [`crate::views::ExtraAction`]
+    Custom {
+        name: &'static str,
+        safe: bool,
+    },
+}
+
+impl Action {
+    /// the action only reads instances
+    pub fn is_safe(&self) -> bool {
+        matches!(
+            self,
+            Self::List | Self::Retrieve | Self::Custom { safe: true, .. }
+        )
+    }
+}
+
+/// identity of an authenticated request, inserted into the request extensions by an authentication layer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub subject: String,
+}
+
+/// the method, uri, headers and extensions of a request, passed to the permission hooks
+#[derive(Debug, Clone)]
+pub struct RequestParts(pub Parts);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestParts {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        Ok(Self(parts.clone()))
+    }
+}
+
+impl OperationInput for RequestParts {}
+
+/// a permission policy of a model view, return [`crate::AppError::Unauthorized`] or
+/// [`crate::AppError::Forbidden`] to deny a request, both are allowed by default
+pub trait Permission<M>: Send + Sync {
+    /// check the request before the action
+    fn has_permission(&self, _action: Action, _parts: &Parts) -> Result<()> {
+        Ok(())
+    }
+
+    /// check the instance of the retrieve, update, partial update or delete action
+    fn has_object_permission(&self, _action: Action, _parts: &Parts, _model: &M) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// allow any request
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAny;
+
+impl<M> Permission<M> for AllowAny {}
+
+/// allow requests with an [`Identity`] only, or return 401
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IsAuthenticated;
+
+impl<M> Permission<M> for IsAuthenticated {
+    fn has_permission(&self, _action: Action, parts: &Parts) -> Result<()> {
+        ensure!(
+            parts.extensions.get::<Identity>().is_some(),
+            UnauthorizedSnafu {
+                reason: "authentication credentials were not provided",
+            }
+        );
+        Ok(())
+    }
+}
+
+/// allow the safe actions only, or return 403
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOnly;
+
+impl<M> Permission<M> for ReadOnly {
+    fn has_permission(&self, action: Action, _parts: &Parts) -> Result<()> {
+        ensure!(
+            action.is_safe(),
+            ForbiddenSnafu {
+                reason: "the resource is read only",
+            }
+        );
+        Ok(())
+    }
+}
+
+/// allow the safe actions for any request, and the others with an [`Identity`] only
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IsAuthenticatedOrReadOnly;
+
+impl<M> Permission<M> for IsAuthenticatedOrReadOnly {
+    fn has_permission(&self, action: Action, parts: &Parts) -> Result<()> {
+        if action.is_safe() {
+            return Ok(());
+        }
+        <IsAuthenticated as Permission<M>>::has_permission(&IsAuthenticated, action, parts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppError;
+    use axum::http::Request;
+
+    fn parts(identity: Option<Identity>) -> Parts {
+        let mut request = Request::builder().uri("/api/tag").body(()).unwrap();
+        if let Some(identity) = identity {
+            request.extensions_mut().insert(identity);
+        }
+        request.into_parts().0
+    }
+
+    #[test]
+    fn builtin_permissions() {
+        let anonymous = parts(None);
+        let user = parts(Some(Identity {
+            subject: "bob".to_owned(),
+        }));
+        let check = |permission: &dyn Permission<()>, action, parts| {
+            permission.has_permission(action, parts)
+        };
+        assert!(check(&AllowAny, Action::Delete, &anonymous).is_ok());
+        assert!(matches!(
+            check(&IsAuthenticated, Action::List, &anonymous),
+            Err(AppError::Unauthorized { .. })
+        ));
+        assert!(check(&IsAuthenticated, Action::Delete, &user).is_ok());
+        assert!(check(&ReadOnly, Action::Retrieve, &anonymous).is_ok());
+        assert!(matches!(
+            check(&ReadOnly, Action::Create, &user),
+            Err(AppError::Forbidden { .. })
+        ));
+        assert!(check(&IsAuthenticatedOrReadOnly, Action::List, &anonymous).is_ok());
+        assert!(check(&IsAuthenticatedOrReadOnly, Action::Update, &anonymous).is_err());
+        assert!(check(&IsAuthenticatedOrReadOnly, Action::Update, &user).is_ok());
+    }
+}
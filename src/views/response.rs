@@ -0,0 +1,8 @@
+/// what a write operation like create or update return in body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseBody {
+    /// return an empty body with the status code only
+    Empty,
+    /// return the instance saved in database as json
+    Instance,
+}
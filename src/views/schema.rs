@@ -0,0 +1,64 @@
+use schemars::JsonSchema;
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// sea-orm model of the entity behind active model `T`
+pub type Model<T> = <<T as ActiveModelTrait>::Entity as EntityTrait>::Model;
+
+/// request and response representations of a [`crate::views::ModelViewExt`],
+/// like serializers in `django-rest-framework`
+///
+/// inputs are changed into the active model with [`IntoActiveModel`],
+/// fields not set by the input will be left to database, so `id` or server managed timestamps
+/// can be omitted from the input types, which can be derived by `sea_orm::DeriveIntoActiveModel`
+/// ```rust,ignore
+/// #[derive(Deserialize, JsonSchema, DeriveIntoActiveModel)]
+/// struct UserCreate {
+///     name: String,
+///     password_hash: String,
+/// }
+///
+/// #[derive(Serialize, JsonSchema)]
+/// struct UserOutput {
+///     id: i32,
+///     name: String,
+/// }
+///
+/// impl From<user::Model> for UserOutput {
+///     fn from(model: user::Model) -> Self {
+///         Self { id: model.id, name: model.name }
+///     }
+/// }
+///
+/// struct UserSchema;
+///
+/// impl ViewSchema<user::ActiveModel> for UserSchema {
+///     type Create = UserCreate;
+///     type Update = UserCreate;
+///     type Output = UserOutput;
+/// }
+///
+/// impl ModelViewExt<user::ActiveModel, UserSchema> for UserView {}
+/// ```
+pub trait ViewSchema<T: ActiveModelTrait> {
+    /// body of create, default is the model
+    type Create: IntoActiveModel<T> + DeserializeOwned + Send;
+    /// body of update, default is the model
+    type Update: IntoActiveModel<T> + DeserializeOwned + Send;
+    /// representation of an instance in responses, default is the model,
+    /// its top level properties are the fields which can be selected with `?fields=`
+    type Output: From<Model<T>> + Serialize + JsonSchema + Send;
+}
+
+/// use the sea-orm model for both requests and responses
+pub struct ModelSchema;
+
+impl<T> ViewSchema<T> for ModelSchema
+where
+    T: ActiveModelTrait,
+    Model<T>: IntoActiveModel<T> + DeserializeOwned + Serialize + JsonSchema + Send,
+{
+    type Create = Model<T>;
+    type Update = Model<T>;
+    type Output = Model<T>;
+}
@@ -0,0 +1,95 @@
+use sea_orm::sea_query::{Alias, Expr, Func, SimpleExpr};
+use sea_orm::{ColumnTrait, Condition, IntoSimpleExpr};
+
+use crate::views::filter::{like, lower};
+
+/// how the search query matches the search fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    /// split search query into terms by whitespace,
+    /// every term must case-insensitive match at least one of the search fields,
+    /// `%` and `_` in a term match themselves
+    #[default]
+    Like,
+    /// postgres only, match `to_tsvector` of the search fields with `plainto_tsquery` of the query
+    FullText { language: &'static str },
+}
+
+impl SearchMode {
+    /// build the search condition, return `None` if there is nothing to search
+    pub fn condition<C: ColumnTrait>(&self, fields: &[C], search: &str) -> Option<Condition> {
+        let search = search.trim();
+        if fields.is_empty() || search.is_empty() {
+            return None;
+        }
+        let condition = match self {
+            SearchMode::Like => {
+                search
+                    .split_whitespace()
+                    .fold(Condition::all(), |condition, term| {
+                        let term = term.to_lowercase();
+                        condition.add(fields.iter().fold(Condition::any(), |any, column| {
+                            any.add(lower(*column).like(like("%", &term, "%")))
+                        }))
+                    })
+            }
+            SearchMode::FullText { language } => {
+                let separator = SimpleExpr::from(" ");
+                let document = Func::cust(Alias::new("concat_ws")).args(
+                    std::iter::once(separator)
+                        .chain(fields.iter().map(|column| column.into_simple_expr())),
+                );
+                Condition::all().add(Expr::cust_with_exprs(
+                    "to_tsvector($1::regconfig, $2) @@ plainto_tsquery($1::regconfig, $3)",
+                    [
+                        SimpleExpr::from(*language),
+                        document.into(),
+                        SimpleExpr::from(search),
+                    ],
+                ))
+            }
+        };
+        Some(condition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::tests_cfg::cake;
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    fn search_sql(mode: SearchMode, search: &str) -> String {
+        let condition = mode
+            .condition(&[cake::Column::Name], search)
+            .unwrap_or_else(Condition::all);
+        cake::Entity::find()
+            .filter(condition)
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn search_into_condition() {
+        assert_eq!(
+            search_sql(SearchMode::Like, " Choco  cake "),
+            r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE LOWER("cake"."name") LIKE '%choco%' ESCAPE E'\\' AND LOWER("cake"."name") LIKE '%cake%' ESCAPE E'\\'"#
+        );
+        assert_eq!(
+            search_sql(SearchMode::Like, "%"),
+            r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE LOWER("cake"."name") LIKE E'%\\%%' ESCAPE E'\\'"#
+        );
+        assert_eq!(
+            search_sql(
+                SearchMode::FullText {
+                    language: "english"
+                },
+                "choco"
+            ),
+            r#"SELECT "cake"."id", "cake"."name" FROM "cake" WHERE to_tsvector('english'::regconfig, concat_ws(' ', "cake"."name")) @@ plainto_tsquery('english'::regconfig, 'choco')"#
+        );
+        assert!(SearchMode::Like
+            .condition(&[cake::Column::Name], " ")
+            .is_none());
+    }
+}
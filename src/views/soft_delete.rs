@@ -0,0 +1,107 @@
+use std::time::SystemTime;
+
+use sea_orm::prelude::ChronoDateTimeUtc;
+use sea_orm::sea_query::ColumnType;
+use sea_orm::{ColumnTrait, Condition, Value};
+use serde_json::Value as JsonValue;
+
+use crate::error::{InternalServerSnafu, Result};
+use crate::views::convert::json_to_value;
+
+/// how an instance is marked as deleted instead of `DELETE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoftDelete<C> {
+    /// a nullable timestamp column like `deleted_at`, set to the time of the deletion,
+    /// the column type must be a timestamp or a big integer of unix seconds
+    Timestamp(C),
+    /// a boolean column like `is_deleted`, set to true
+    Flag(C),
+}
+
+impl<C: ColumnTrait> SoftDelete<C> {
+    pub fn column(&self) -> C {
+        match self {
+            Self::Timestamp(column) | Self::Flag(column) => *column,
+        }
+    }
+
+    /// condition of the instances not deleted
+    pub fn alive_condition(&self) -> Condition {
+        match self {
+            Self::Timestamp(column) => Condition::all().add(column.is_null()),
+            Self::Flag(column) => Condition::all().add(column.eq(false)),
+        }
+    }
+
+    /// value of the column to mark an instance deleted
+    pub fn deleted_value(&self) -> Result<Value> {
+        let column = match self {
+            Self::Flag(column) => return json_to_value(column, &JsonValue::Bool(true)),
+            Self::Timestamp(column) => column,
+        };
+        let now = ChronoDateTimeUtc::from(SystemTime::now());
+        match column.def().get_column_type() {
+            ColumnType::TimestampWithTimeZone => Ok(now.fixed_offset().into()),
+            ColumnType::DateTime | ColumnType::Timestamp => Ok(now.naive_utc().into()),
+            ColumnType::BigInteger => Ok(now.timestamp().into()),
+            col_type => {
+                tracing::error!(
+                    "soft delete column `{}` of type {col_type:?} is not a timestamp",
+                    column.as_str()
+                );
+                InternalServerSnafu.fail()
+            }
+        }
+    }
+
+    /// value of the column to restore a deleted instance
+    pub fn restored_value(&self) -> Result<Value> {
+        match self {
+            Self::Timestamp(column) => json_to_value(column, &JsonValue::Null),
+            Self::Flag(column) => json_to_value(column, &JsonValue::Bool(false)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests_cfg::note;
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QuerySelect, QueryTrait};
+
+    #[test]
+    fn mark_and_filter_deleted() {
+        let sql = |soft_delete: SoftDelete<note::Column>| {
+            note::Entity::find()
+                .select_only()
+                .column(note::Column::Id)
+                .filter(soft_delete.alive_condition())
+                .build(DbBackend::Postgres)
+                .to_string()
+        };
+        assert_eq!(
+            sql(SoftDelete::Timestamp(note::Column::DeletedAt)),
+            r#"SELECT "note"."id" FROM "note" WHERE "note"."deleted_at" IS NULL"#
+        );
+        assert_eq!(
+            sql(SoftDelete::Flag(note::Column::IsArchived)),
+            r#"SELECT "note"."id" FROM "note" WHERE "note"."is_archived" = FALSE"#
+        );
+
+        let timestamp = SoftDelete::Timestamp(note::Column::DeletedAt);
+        assert!(matches!(
+            timestamp.deleted_value().unwrap(),
+            Value::ChronoDateTimeWithTimeZone(Some(_))
+        ));
+        assert_eq!(
+            timestamp.restored_value().unwrap(),
+            Value::ChronoDateTimeWithTimeZone(None)
+        );
+        let flag = SoftDelete::Flag(note::Column::IsArchived);
+        assert_eq!(flag.deleted_value().unwrap(), Value::Bool(Some(true)));
+        assert_eq!(flag.restored_value().unwrap(), Value::Bool(Some(false)));
+        assert!(SoftDelete::Timestamp(note::Column::Content)
+            .deleted_value()
+            .is_err());
+    }
+}
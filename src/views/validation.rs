@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use aide::gen::GenContext;
+use aide::openapi::Operation;
+use aide::OperationInput;
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Request};
+use axum::http::header;
+use axum::Json;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, InvalidBodySnafu, Result, ValidationSnafu};
+
+/// key of the errors not belong to a field, like a body which is not a json object
+pub const NON_FIELD_ERRORS: &str = "non_field_errors";
+
+/// messages of every invalid field, rendered as `{"errors": {"age": ["must be >= 0"]}}`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ValidationErrors {
+    pub errors: BTreeMap<String, Vec<String>>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors
+            .entry(field.into())
+            .or_default()
+            .push(message.into());
+    }
+
+    /// add the message if `valid` is false
+    pub fn check(&mut self, valid: bool, field: impl Into<String>, message: impl Into<String>) {
+        if !valid {
+            self.add(field, message);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// return [`AppError::Validation`] if any error is added
+    pub fn into_result(self) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        ValidationSnafu { errors: self }.fail()
+    }
+}
+
+impl Display for ValidationErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let errors = self
+            .errors
+            .iter()
+            .map(|(field, messages)| format!("{field}: {}", messages.join(", ")))
+            .collect::<Vec<_>>();
+        write!(f, "{}", errors.join("; "))
+    }
+}
+
+/// convert a deserialization error of the json body into [`ValidationErrors`] of the field path,
+/// or 400 if the body is not a valid json
+fn deserialize_error(error: serde_path_to_error::Error<serde_json::Error>) -> AppError {
+    let path = error.path().to_string();
+    let inner = error.into_inner();
+    if !inner.is_data() {
+        return InvalidBodySnafu {
+            reason: inner.to_string(),
+        }
+        .build();
+    }
+    let message = inner.to_string();
+    // serde reports line and column of the body which mean nothing to a field
+    let message = match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_owned(),
+        None => message,
+    };
+    let mut errors = ValidationErrors::new();
+    match message
+        .strip_prefix("missing field `")
+        .and_then(|field| field.strip_suffix('`'))
+    {
+        Some(field) if path == "." => errors.add(field, "this field is required"),
+        Some(field) => errors.add(format!("{path}.{field}"), "this field is required"),
+        None if path == "." => errors.add(NON_FIELD_ERRORS, message),
+        None => errors.add(path, message),
+    }
+    ValidationSnafu { errors }.build()
+}
+
+/// deserialize a json string into `T`, errors are reported by the field path
+pub fn from_json_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(deserialize_error)?;
+    deserializer.end().map_err(|e| {
+        InvalidBodySnafu {
+            reason: e.to_string(),
+        }
+        .build()
+    })?;
+    Ok(value)
+}
+
+/// deserialize a json value into `T`, errors are reported by the field path
+pub fn from_json_value<T: DeserializeOwned>(value: serde_json::Value) -> Result<T> {
+    serde_path_to_error::deserialize(value).map_err(deserialize_error)
+}
+
+/// json body extractor like [`axum::Json`], but rejections are [`AppError`] in json,
+/// a body not match `T` returns 400 with [`ValidationErrors`] of the fields
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self> {
+        let is_json = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|mime| {
+                let mime = mime.trim();
+                mime.eq_ignore_ascii_case("application/json") || mime.ends_with("+json")
+            });
+        if !is_json {
+            return InvalidBodySnafu {
+                reason: "expect request with `Content-Type: application/json`",
+            }
+            .fail();
+        }
+        let bytes = Bytes::from_request(req, state).await.map_err(|e| {
+            InvalidBodySnafu {
+                reason: e.body_text(),
+            }
+            .build()
+        })?;
+        from_json_slice(&bytes).map(Self)
+    }
+}
+
+impl<T: JsonSchema> OperationInput for JsonBody<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        <Json<T> as OperationInput>::operation_input(ctx, operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Student {
+        name: String,
+        age: u8,
+    }
+
+    fn errors(error: AppError) -> BTreeMap<String, Vec<String>> {
+        match error {
+            AppError::Validation { errors, .. } => errors.errors,
+            other => panic!("expect validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn field_errors_of_body() {
+        let error = from_json_slice::<Student>(br#"{"name": "bob", "age": -1}"#).unwrap_err();
+        assert_eq!(errors(error)["age"].len(), 1);
+        let error = from_json_value::<Student>(json!({"age": 1})).unwrap_err();
+        assert_eq!(errors(error)["name"], ["this field is required"]);
+        let error = from_json_value::<Student>(json!([])).unwrap_err();
+        assert!(errors(error).contains_key(NON_FIELD_ERRORS));
+        assert!(matches!(
+            from_json_slice::<Student>(b"{").unwrap_err(),
+            AppError::InvalidBody { .. }
+        ));
+
+        let mut errors = ValidationErrors::new();
+        errors.check(true, "name", "must not be empty");
+        assert!(errors.clone().into_result().is_ok());
+        errors.check(false, "age", "must be >= 0");
+        assert_eq!(
+            serde_json::to_value(&errors).unwrap(),
+            json!({"errors": {"age": ["must be >= 0"]}})
+        );
+        assert!(errors.into_result().is_err());
+    }
+}